@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use chip_eight::Chip8;
+
+// About five seconds of emulated time
+const FRAMES: u32 = 300;
+
+// Any byte string is a ROM: the emulator may reject it or stop on an
+// exception, but must never panic
+fuzz_target!(|rom: &[u8]| {
+    let mut c8 = match Chip8::from_rom(rom) {
+        Ok(c8) => c8,
+        Err(_) => return,
+    };
+    for _ in 0..FRAMES {
+        if c8.run_frame().is_err() {
+            break;
+        }
+    }
+});
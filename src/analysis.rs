@@ -0,0 +1,282 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use crate::analysis::cfg::ControlFlowGraph;
+use crate::analysis::map::MemoryMap;
+use crate::chip8::instruction::{Instruction, Platform};
+use crate::chip8::memory::RAM_MAX;
+use crate::chip8::memory::layout::PROGRAM_START;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{File as FileError, RomTooLarge};
+use crate::options::Options;
+
+pub(crate) mod cfg;
+pub(crate) mod coverage;
+pub(crate) mod diff;
+pub(crate) mod lint;
+pub(crate) mod map;
+pub(crate) mod sprites;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    Next,
+    Skip,
+    Jump,
+    Call,
+    // From a call to the instruction after it, once the subroutine returns
+    Return,
+}
+
+pub struct Node {
+    pub opcode: u16,
+    pub platform: Platform,
+    pub instruction: Option<Instruction>,
+    pub successors: Vec<(u16, Edge)>,
+}
+
+// Static walk of a ROM from its entry point, following every jump, call and
+// skip. The value of I is tracked along the way when it is known, to tell
+// which bytes are read as data and which ones get overwritten.
+pub struct Analysis {
+    pub rom_size: usize,
+    pub nodes: BTreeMap<u16, Node>,
+    pub data: BTreeSet<u16>,
+    pub written: BTreeSet<u16>,
+    // Bnnn jumps, whose targets depend on V0 and aren't followed
+    pub computed_jumps: Vec<u16>,
+    // Jumps and calls out of the ROM, as (from, target)
+    pub outside: Vec<(u16, u16)>,
+    // Stores which overwrite code, as (store, first code byte written)
+    pub self_modifying: Vec<(u16, u16)>,
+    // Reads and writes through I going past the end of the RAM, as
+    // (instruction, I)
+    pub past_ram: Vec<(u16, u16)>,
+    stores: Vec<(u16, u16)>,
+}
+
+impl Analysis {
+    // Fails when the ROM doesn't fit after the interpreter, as when running it
+    pub fn new(rom: &[u8]) -> Result<Analysis, Exception> {
+        let space = RAM_MAX - PROGRAM_START as usize;
+        if rom.len() > space {
+            return Err(Exception::new(RomTooLarge { size: rom.len(), space }));
+        }
+
+        let mut analysis = Analysis {
+            rom_size: rom.len(),
+            nodes: BTreeMap::new(),
+            data: BTreeSet::new(),
+            written: BTreeSet::new(),
+            computed_jumps: Vec::new(),
+            outside: Vec::new(),
+            self_modifying: Vec::new(),
+            past_ram: Vec::new(),
+            stores: Vec::new(),
+        };
+
+        // Value of I when reaching each instruction, None if it isn't known
+        let mut states: BTreeMap<u16, Option<u16>> = BTreeMap::new();
+        let mut queue = VecDeque::from([(PROGRAM_START, Some(0))]);
+
+        while let Some((address, i)) = queue.pop_front() {
+            match states.get(&address) {
+                Some(&known) if known == i || known.is_none() => continue,
+                Some(_) => { states.insert(address, None); }
+                None => { states.insert(address, i); }
+            }
+            let i = states[&address];
+
+            let opcode = ((analysis.byte(rom, address) as u16) << 8) | analysis.byte(rom, address + 1) as u16;
+            let platform = Platform::of(opcode);
+            let instruction = Instruction::decode(opcode);
+            let successors = if opcode == 0x00FD {
+                // SCHIP exit
+                Vec::new()
+            } else if platform != Platform::Chip8 && !matches!(instruction, Some(Instruction::Draw(..))) {
+                // The later platforms' opcodes are stepped over
+                analysis.follow(address, vec![(address + Platform::length(opcode), Edge::Next)])
+            } else {
+                match instruction {
+                    Some(instruction) => analysis.successors(address, instruction),
+                    None => Vec::new(),
+                }
+            };
+            let next_i = match instruction {
+                Some(instruction) => analysis.access_memory(address, instruction, i),
+                None => None,
+            };
+
+            for &(target, edge) in &successors {
+                let target_i = if edge == Edge::Return { None } else { next_i };
+                queue.push_back((target, target_i));
+            }
+            analysis.nodes.insert(address, Node { opcode, platform, instruction, successors });
+        }
+
+        // Instructions revisited with a different I are walked twice
+        analysis.computed_jumps.sort();
+        analysis.computed_jumps.dedup();
+        analysis.outside.sort();
+        analysis.outside.dedup();
+        analysis.past_ram.sort();
+        analysis.past_ram.dedup();
+        analysis.stores.sort();
+        for &(store, target) in &analysis.stores {
+            if analysis.is_code(target) && analysis.self_modifying.last().map(|&(last, _)| last) != Some(store) {
+                analysis.self_modifying.push((store, target));
+            }
+        }
+        Ok(analysis)
+    }
+
+    pub fn in_rom(&self, address: u16) -> bool {
+        address >= PROGRAM_START && ((address - PROGRAM_START) as usize) < self.rom_size
+    }
+
+    pub fn is_code(&self, address: u16) -> bool {
+        self.nodes.contains_key(&address)
+            || (address > 0 && self.nodes.contains_key(&(address - 1)))
+    }
+
+    // Bytes written through I, as (store, target)
+    pub fn stores(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.stores.iter().copied()
+    }
+
+    // Invalid opcodes reached by the walk
+    pub fn invalid(&self) -> Vec<(u16, u16)> {
+        self.nodes.iter()
+            .filter(|(_, node)| node.instruction.is_none() && node.platform == Platform::Chip8)
+            .map(|(&address, node)| (address, node.opcode))
+            .collect()
+    }
+
+    fn byte(&self, rom: &[u8], address: u16) -> u8 {
+        if self.in_rom(address) {
+            rom[(address - PROGRAM_START) as usize]
+        } else {
+            0
+        }
+    }
+
+    fn successors(&mut self, address: u16, instruction: Instruction) -> Vec<(u16, Edge)> {
+        let next = address + 2;
+        let targets = match instruction {
+            Instruction::Ret => Vec::new(),
+            Instruction::Sys(target) | Instruction::Jump(target) => vec![(target, Edge::Jump)],
+            Instruction::Call(target) => vec![(target, Edge::Call), (next, Edge::Return)],
+            Instruction::JumpV0(_) => {
+                self.computed_jumps.push(address);
+                Vec::new()
+            }
+            Instruction::SkipEq(..) | Instruction::SkipNe(..) | Instruction::SkipEqReg(..)
+            | Instruction::SkipNeReg(..) | Instruction::SkipKey(_) | Instruction::SkipNoKey(_) => {
+                vec![(next, Edge::Next), (next + 2, Edge::Skip)]
+            }
+            _ => vec![(next, Edge::Next)],
+        };
+        self.follow(address, targets)
+    }
+
+    // Keeps the targets inside the ROM, reporting the others
+    fn follow(&mut self, address: u16, targets: Vec<(u16, Edge)>) -> Vec<(u16, Edge)> {
+        targets.into_iter()
+            .filter(|&(target, _)| {
+                let inside = self.in_rom(target) && self.in_rom(target + 1);
+                if !inside {
+                    self.outside.push((address, target));
+                }
+                inside
+            })
+            .collect()
+    }
+
+    // Records the bytes read and written through I, and returns the value of
+    // I after the instruction
+    fn access_memory(&mut self, address: u16, instruction: Instruction, i: Option<u16>) -> Option<u16> {
+        let length = match instruction {
+            Instruction::Draw(_, _, n) => n as usize,
+            Instruction::LoadRegs(x) | Instruction::Store(x) => x as usize + 1,
+            Instruction::StoreBcd(_) => 3,
+            _ => 0,
+        };
+        if let Some(i) = i {
+            if i as usize + length > RAM_MAX {
+                self.past_ram.push((address, i));
+            }
+        }
+
+        let range = |start: u16, length: u16| (start..start.saturating_add(length)).filter(|&a| (a as usize) < RAM_MAX);
+        match (instruction, i) {
+            (Instruction::LoadI(nnn), _) => Some(nnn),
+            (Instruction::AddI(_) | Instruction::LoadFont(_), _) => None,
+            (Instruction::Draw(_, _, n), Some(i)) => {
+                self.data.extend(range(i, n as u16));
+                Some(i)
+            }
+            (Instruction::LoadRegs(x), Some(i)) => {
+                self.data.extend(range(i, x as u16 + 1));
+                Some(i)
+            }
+            (Instruction::Store(x), Some(i)) => {
+                self.store(address, range(i, x as u16 + 1));
+                Some(i)
+            }
+            (Instruction::StoreBcd(_), Some(i)) => {
+                self.store(address, range(i, 3));
+                Some(i)
+            }
+            (_, i) => i,
+        }
+    }
+
+    fn store(&mut self, address: u16, targets: impl Iterator<Item = u16>) {
+        for target in targets {
+            self.written.insert(target);
+            self.stores.push((address, target));
+        }
+    }
+}
+
+// `analyze` command: prints a summary and the code/data map of the ROM, and
+// writes its control flow graph as Graphviz DOT
+pub fn run(options: &Options) -> Result<(), Exception> {
+    let rom = fs::read(&options.rom).map_err(|_| Exception::new(FileError))?;
+    let analysis = Analysis::new(&rom)?;
+    let graph = ControlFlowGraph::new(&analysis);
+    let map = MemoryMap::new(&analysis);
+
+    println!("{}: {} bytes, {} instructions reached in {} blocks",
+             options.rom, rom.len(), analysis.nodes.len(), graph.blocks.len());
+    for (address, opcode) in analysis.invalid() {
+        println!("  invalid instruction {:04X} at {:#05X}", opcode, address);
+    }
+    for (from, target) in &analysis.outside {
+        println!("  jump out of the ROM to {:#05X} at {:#05X}", target, from);
+    }
+    for address in &analysis.computed_jumps {
+        println!("  computed jump at {:#05X}, its targets aren't followed", address);
+    }
+    for (address, i) in &analysis.past_ram {
+        println!("  access past the end of the RAM from I = {:#05X} at {:#05X}", i, address);
+    }
+    for (store, target) in &analysis.self_modifying {
+        println!("  self-modifying store at {:#05X} overwrites code at {:#05X}", store, target);
+    }
+    let unreached = map.unreached();
+    if unreached > 0 {
+        println!("  {} bytes never reached as code nor data", unreached);
+    }
+
+    match &options.map {
+        Some(path) => {
+            fs::write(path, map.to_string()).map_err(|_| Exception::new(FileError))?;
+            println!("Map saved to {}", path);
+        }
+        None => print!("\n{}", map),
+    }
+    if let Some(path) = &options.dot {
+        fs::write(path, graph.to_dot(&analysis)).map_err(|_| Exception::new(FileError))?;
+        println!("Graph saved to {}", path);
+    }
+    Ok(())
+}
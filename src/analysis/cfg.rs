@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use crate::analysis::{Analysis, Edge};
+use crate::chip8::memory::layout::PROGRAM_START;
+use crate::chip8::instruction::Platform;
+
+pub struct Block {
+    pub instructions: Vec<u16>,
+    pub successors: Vec<(u16, Edge)>,
+}
+
+// Basic blocks of the reached instructions, keyed by their first address
+pub struct ControlFlowGraph {
+    pub blocks: BTreeMap<u16, Block>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(analysis: &Analysis) -> ControlFlowGraph {
+        let mut predecessors: BTreeMap<u16, Vec<(u16, Edge)>> = BTreeMap::new();
+        for (&address, node) in &analysis.nodes {
+            for &(target, edge) in &node.successors {
+                predecessors.entry(target).or_default().push((address, edge));
+            }
+        }
+
+        // A block starts wherever the flow doesn't only come from the
+        // previous instruction
+        let is_leader = |address: u16| {
+            match predecessors.get(&address).map(Vec::as_slice) {
+                Some(&[(from, Edge::Next)]) => address == PROGRAM_START || analysis.nodes[&from].successors.len() > 1,
+                _ => true,
+            }
+        };
+
+        let mut blocks = BTreeMap::new();
+        for &start in analysis.nodes.keys().filter(|&&address| is_leader(address)) {
+            let mut instructions = vec![start];
+            let mut address = start;
+            loop {
+                match analysis.nodes[&address].successors.as_slice() {
+                    &[(next, Edge::Next)] if !is_leader(next) => {
+                        instructions.push(next);
+                        address = next;
+                    }
+                    successors => {
+                        blocks.insert(start, Block { instructions, successors: successors.to_vec() });
+                        break;
+                    }
+                }
+            }
+        }
+        ControlFlowGraph { blocks }
+    }
+
+    pub fn to_dot(&self, analysis: &Analysis) -> String {
+        let mut dot = String::from("digraph rom {\n    node [shape=box, fontname=monospace];\n");
+        for (start, block) in &self.blocks {
+            let mut label = String::new();
+            let mut color = "black";
+            for address in &block.instructions {
+                let node = &analysis.nodes[address];
+                match node.instruction {
+                    _ if node.platform != Platform::Chip8 => {
+                        write!(label, "{:#05X}: {} {:04X}\\l", address, node.platform, node.opcode).unwrap();
+                    }
+                    Some(instruction) => write!(label, "{:#05X}: {}\\l", address, instruction).unwrap(),
+                    None => {
+                        write!(label, "{:#05X}: invalid {:04X}\\l", address, node.opcode).unwrap();
+                        color = "red";
+                    }
+                }
+                if analysis.computed_jumps.contains(address) {
+                    color = "orange";
+                }
+            }
+            writeln!(dot, "    b{:03X} [label=\"{}\", color={}];", start, label, color).unwrap();
+
+            for (target, edge) in &block.successors {
+                let style = match edge {
+                    Edge::Next => "",
+                    Edge::Skip => " [label=skip]",
+                    Edge::Jump => " [label=jump]",
+                    Edge::Call => " [label=call, style=dashed]",
+                    Edge::Return => " [label=return, style=dotted]",
+                };
+                writeln!(dot, "    b{:03X} -> b{:03X}{};", start, target, style).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use crate::analysis::Analysis;
+use crate::chip8::memory::layout::PROGRAM_START;
+use crate::chip8::coverage::Coverage;
+use crate::chip8::instruction::{Instruction, Platform};
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, File as FileError};
+use crate::options::Options;
+
+// Source location of each instruction, read from `address file:line` lines
+struct SourceMap {
+    lines: BTreeMap<u16, (String, u32)>,
+}
+
+impl SourceMap {
+    fn load(path: &str) -> Result<SourceMap, Exception> {
+        let content = fs::read_to_string(path).map_err(|_| Exception::new(FileError))?;
+        let mut lines = BTreeMap::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (address, location) = line.split_once(char::is_whitespace).ok_or(Exception::new(BadArgument))?;
+            let (file, number) = location.trim().rsplit_once(':').ok_or(Exception::new(BadArgument))?;
+            let address = u16::from_str_radix(address.trim_start_matches("0x"), 16)
+                .map_err(|_| Exception::new(BadArgument))?;
+            let number = number.parse().map_err(|_| Exception::new(BadArgument))?;
+            lines.insert(address, (file.to_string(), number));
+        }
+        Ok(SourceMap { lines })
+    }
+}
+
+// Adds the run to the coverage file, then prints the annotated disassembly
+// and writes the lcov report
+pub fn report(options: &Options, coverage: &mut Coverage) -> Result<(), Exception> {
+    let path = match &options.coverage {
+        Some(path) => Path::new(path),
+        None => return Ok(()),
+    };
+    coverage.merge_file(path)?;
+    coverage.save(path)?;
+
+    let rom = fs::read(&options.rom).map_err(|_| Exception::new(FileError))?;
+    let analysis = Analysis::new(&rom)?;
+
+    // Instructions found statically, plus the ones only reached at run time
+    // such as the targets of computed jumps
+    let mut instructions: BTreeMap<u16, Instruction> = analysis.nodes.iter()
+        .filter(|(_, node)| node.platform == Platform::Chip8)
+        .filter_map(|(&address, node)| node.instruction.map(|instruction| (address, instruction)))
+        .collect();
+    for &address in coverage.executed.keys() {
+        let offset = address.wrapping_sub(PROGRAM_START) as usize;
+        if let Some(bytes) = rom.get(offset..offset + 2) {
+            if let Some(instruction) = Instruction::decode(((bytes[0] as u16) << 8) | bytes[1] as u16) {
+                instructions.insert(address, instruction);
+            }
+        }
+    }
+
+    let is_skip = |instruction: &Instruction| matches!(instruction,
+        Instruction::SkipEq(..) | Instruction::SkipNe(..) | Instruction::SkipEqReg(..)
+        | Instruction::SkipNeReg(..) | Instruction::SkipKey(_) | Instruction::SkipNoKey(_));
+    let hits = |address: &u16| coverage.executed.get(address).copied().unwrap_or(0);
+
+    for (address, instruction) in &instructions {
+        let count = match hits(address) {
+            0 => String::from("#####"),
+            count => count.to_string(),
+        };
+        let mut line = format!("{:>10}  {:#05X}  {}", count, address, instruction);
+        if is_skip(instruction) {
+            let (taken, not_taken) = coverage.branches.get(address).copied().unwrap_or((0, 0));
+            write!(line, "    (skipped {}, not skipped {})", taken, not_taken).unwrap();
+        }
+        println!("{}", line);
+    }
+    let data_in_rom = coverage.data.keys()
+        .filter(|&&address| address >= PROGRAM_START && ((address - PROGRAM_START) as usize) < rom.len())
+        .count();
+
+    let executed = instructions.keys().filter(|address| hits(address) > 0).count();
+    let branches: Vec<(u64, u64)> = instructions.iter()
+        .filter(|(_, instruction)| is_skip(instruction))
+        .map(|(address, _)| coverage.branches.get(address).copied().unwrap_or((0, 0)))
+        .collect();
+    let branches_hit = branches.iter().map(|&(taken, not_taken)| (taken > 0) as usize + (not_taken > 0) as usize).sum::<usize>();
+    println!("Coverage: {}/{} instructions ({:.1}%), {}/{} branches, {} ROM bytes read as data",
+             executed, instructions.len(), 100.0 * executed as f64 / instructions.len().max(1) as f64,
+             branches_hit, branches.len() * 2, data_in_rom);
+
+    if let Some(lcov_path) = &options.lcov {
+        let source_map = match &options.source_map {
+            Some(map_path) => Some(SourceMap::load(map_path)?),
+            None => None,
+        };
+        let lcov = lcov(&instructions, coverage, source_map.as_ref(), &options.rom, is_skip);
+        fs::write(lcov_path, lcov).map_err(|_| Exception::new(FileError))?;
+        println!("Coverage report saved to {}", lcov_path);
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Line {
+    hits: u64,
+    // Skips on the line, as (block, counts if executed)
+    branches: Vec<(u16, Option<(u64, u64)>)>,
+}
+
+// lcov tracefile, with the ROM addresses as line numbers when there is no
+// source map
+fn lcov(instructions: &BTreeMap<u16, Instruction>, coverage: &Coverage, source_map: Option<&SourceMap>,
+        rom: &str, is_skip: impl Fn(&Instruction) -> bool) -> String {
+    let mut files: BTreeMap<String, BTreeMap<u32, Line>> = BTreeMap::new();
+    for (address, instruction) in instructions {
+        let (file, line) = match source_map {
+            Some(map) => match map.lines.get(address) {
+                Some((file, line)) => (file.clone(), *line),
+                None => continue,
+            },
+            None => (rom.to_string(), *address as u32),
+        };
+        let hits = coverage.executed.get(address).copied().unwrap_or(0);
+        let entry = files.entry(file).or_default().entry(line).or_default();
+        entry.hits = entry.hits.max(hits);
+        if is_skip(instruction) {
+            let counts = (hits > 0).then(|| coverage.branches.get(address).copied().unwrap_or((0, 0)));
+            entry.branches.push((*address, counts));
+        }
+    }
+
+    let mut lcov = String::from("TN:\n");
+    for (file, lines) in &files {
+        writeln!(lcov, "SF:{}", file).unwrap();
+        let (mut found, mut hit) = (0, 0);
+        for (number, line) in lines {
+            for (block, counts) in &line.branches {
+                let [taken, not_taken] = match counts {
+                    Some((taken, not_taken)) => [taken.to_string(), not_taken.to_string()],
+                    None => [String::from("-"), String::from("-")],
+                };
+                writeln!(lcov, "BRDA:{},{},0,{}", number, block, taken).unwrap();
+                writeln!(lcov, "BRDA:{},{},1,{}", number, block, not_taken).unwrap();
+                found += 2;
+                hit += counts.map_or(0, |(taken, not_taken)| (taken > 0) as u32 + (not_taken > 0) as u32);
+            }
+        }
+        writeln!(lcov, "BRF:{}\nBRH:{}", found, hit).unwrap();
+        for (number, line) in lines {
+            writeln!(lcov, "DA:{},{}", number, line.hits).unwrap();
+        }
+        let lines_hit = lines.values().filter(|line| line.hits > 0).count();
+        writeln!(lcov, "LF:{}\nLH:{}\nend_of_record", lines.len(), lines_hit).unwrap();
+    }
+    lcov
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::fs;
+use crate::chip8::Chip8;
+use crate::chip8::instruction::Instruction;
+use crate::chip8::tracer::CpuState;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, File as FileError};
+use crate::options::Options;
+
+// Instructions shown before a divergence
+const HISTORY: usize = 8;
+
+// One line of the reference trace, `KEY=value` fields in hexadecimal as
+// written by `--trace-format state`. Missing fields aren't compared, so that
+// traces from emulators which don't expose everything can be used.
+struct Expected {
+    fields: Vec<(String, u16)>,
+}
+
+impl Expected {
+    fn parse(line: &str) -> Result<Expected, Exception> {
+        let mut fields = Vec::new();
+        for field in line.split_whitespace() {
+            let (key, value) = field.split_once('=').ok_or(Exception::new(BadArgument))?;
+            let key = key.to_uppercase();
+            if key == "V" {
+                for (register, pair) in value.as_bytes().chunks(2).enumerate().take(16) {
+                    let pair = std::str::from_utf8(pair).map_err(|_| Exception::new(BadArgument))?;
+                    let value = u16::from_str_radix(pair, 16).map_err(|_| Exception::new(BadArgument))?;
+                    fields.push((format!("V{:X}", register), value));
+                }
+            } else {
+                let value = u16::from_str_radix(value, 16).map_err(|_| Exception::new(BadArgument))?;
+                fields.push((key, value));
+            }
+        }
+        Ok(Expected { fields })
+    }
+
+    // Fields whose value differs, as (name, expected, actual)
+    fn differences(&self, state: &CpuState) -> Vec<(String, u16, Option<u16>)> {
+        self.fields.iter()
+            .map(|(key, expected)| (key.clone(), *expected, Self::actual(state, key)))
+            .filter(|(_, expected, actual)| *actual != Some(*expected))
+            .collect()
+    }
+
+    fn actual(state: &CpuState, key: &str) -> Option<u16> {
+        match key {
+            "PC" => Some(state.pc),
+            "I" => Some(state.i),
+            "SP" => Some(state.sp as u16),
+            "DT" => Some(state.dt as u16),
+            "ST" => Some(state.st as u16),
+            _ => key.strip_prefix('V')
+                .and_then(|register| usize::from_str_radix(register, 16).ok())
+                .and_then(|register| state.v.get(register))
+                .map(|&v| v as u16),
+        }
+    }
+}
+
+// `diff` command: runs the ROM one instruction at a time and compares the
+// CPU state after each one with the reference trace, stopping at the first
+// divergence. Returns whether the emulator matched the reference.
+pub fn run(options: &Options, chip8: &mut Chip8) -> Result<bool, Exception> {
+    let path = options.reference.as_ref().ok_or(Exception::new(BadArgument))?;
+    let content = fs::read_to_string(path).map_err(|_| Exception::new(FileError))?;
+    let reference = content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(Expected::parse)
+        .collect::<Result<Vec<Expected>, Exception>>()?;
+    let steps = options.steps.unwrap_or(reference.len() as u64);
+
+    let mut history: VecDeque<String> = VecDeque::with_capacity(HISTORY);
+    for step in 1..=steps {
+        let expected = match reference.get(step as usize - 1) {
+            Some(expected) => expected,
+            None => {
+                println!("The reference trace ends after {} steps", reference.len());
+                break;
+            }
+        };
+        let address = chip8.state().pc;
+        let opcode = match (chip8.peek(address), chip8.peek(address.wrapping_add(1))) {
+            (Some(high), Some(low)) => ((high as u16) << 8) | low as u16,
+            _ => 0,
+        };
+        let mnemonic = Instruction::decode(opcode).map_or(String::from("invalid"), |instruction| instruction.to_string());
+        let executed = format!("step {:<8} frame {:<6} {:03X}  {:04X}  {}", step, chip8.frame(), address, opcode, mnemonic);
+        let result = chip8.step();
+
+        let differences = match &result {
+            Ok(()) => expected.differences(&chip8.state()),
+            Err(_) => Vec::new(),
+        };
+
+        if result.is_err() || !differences.is_empty() {
+            println!("Divergence at {}", executed);
+            if let Err(e) = result {
+                println!("  the emulator stopped: {}", e);
+            }
+            if !differences.is_empty() {
+                println!("  {:<6} {:>9} {:>9}", "", "reference", "emulator");
+                for (key, expected, actual) in differences {
+                    let actual = actual.map_or(String::from("-"), |actual| format!("{:X}", actual));
+                    println!("  {:<6} {:>9X} {:>9}", key, expected, actual);
+                }
+            }
+            if !chip8.call_stack().frames().is_empty() {
+                print!("{}", chip8.call_stack());
+            }
+            if !history.is_empty() {
+                println!("Previous instructions:");
+                for line in &history {
+                    println!("  {}", line);
+                }
+            }
+            return Ok(false);
+        }
+
+        if history.len() == HISTORY {
+            history.pop_front();
+        }
+        history.push_back(executed);
+    }
+
+    println!("{} steps match the reference", steps.min(reference.len() as u64));
+    Ok(true)
+}
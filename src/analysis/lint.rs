@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use crate::analysis::{Analysis, Edge};
+use crate::chip8::memory::layout::PROGRAM_START;
+use crate::chip8::font::FONT_SIZE;
+use crate::chip8::instruction::{Instruction, Platform};
+use crate::chip8::memory::RAM_MAX;
+use crate::chip8::memory::layout::MemoryLayout;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::File as FileError;
+use crate::options::Options;
+
+// Instructions followed after a load or store to look for a reuse of I
+const REUSE_DEPTH: usize = 16;
+
+pub struct Lint {
+    // Reached opcodes of each platform
+    pub platforms: BTreeMap<Platform, Vec<(u16, u16)>>,
+    pub warnings: Vec<(u16, String)>,
+    // Whether the ROM has shifts of VY, reuses of I after a load or store,
+    // and computed jumps, each depending on a quirk
+    shifts: bool,
+    reuses_i: bool,
+    jumps: bool,
+}
+
+impl Lint {
+    pub fn new(analysis: &Analysis, font_address: u16) -> Lint {
+        let mut lint = Lint {
+            platforms: BTreeMap::new(),
+            warnings: Vec::new(),
+            shifts: false,
+            reuses_i: false,
+            jumps: false,
+        };
+
+        for (&address, node) in &analysis.nodes {
+            if node.instruction.is_some() || node.platform != Platform::Chip8 {
+                lint.platforms.entry(node.platform).or_default().push((address, node.opcode));
+            }
+            match node.instruction {
+                Some(Instruction::ShiftRight(x, y) | Instruction::ShiftLeft(x, y)) if x != y => {
+                    lint.warn(address, "shifts VY into VX, the shift quirk decides which register is shifted");
+                    lint.shifts = true;
+                }
+                Some(Instruction::Store(_) | Instruction::LoadRegs(_)) => {
+                    if let Some(reuse) = Self::reuse_of_i(analysis, address) {
+                        lint.warn(address, &format!(
+                            "I is used again at {:#05X}, the load/store quirk decides whether it was incremented", reuse));
+                        lint.reuses_i = true;
+                    }
+                }
+                Some(Instruction::JumpV0(_)) => {
+                    lint.warn(address, "computed jump, the jump quirk decides whether V0 or VX is added");
+                    lint.jumps = true;
+                }
+                _ => {}
+            }
+        }
+
+        // Only the first byte each store overwrites is reported
+        let mut reported = BTreeSet::new();
+        for (store, target) in analysis.stores() {
+            let area = if (font_address..font_address + FONT_SIZE).contains(&target) {
+                "the font"
+            } else if target < PROGRAM_START {
+                "the interpreter area"
+            } else {
+                continue;
+            };
+            if reported.insert(store) {
+                lint.warn(store, &format!("writes into {} at {:#05X}", area, target));
+            }
+        }
+        for &(from, target) in &analysis.outside {
+            lint.warn(from, &format!("jumps out of the ROM to {:#05X}", target));
+        }
+        for &(address, i) in &analysis.past_ram {
+            lint.warn(address, &format!("accesses memory past {:#05X} from I = {:#05X}", 0xFFF, i));
+        }
+        for (address, opcode) in analysis.invalid() {
+            lint.warn(address, &format!("invalid instruction {:04X}", opcode));
+        }
+        lint.warnings.sort();
+        lint.warnings.dedup();
+        lint
+    }
+
+    // Latest platform whose opcodes are used
+    pub fn platform(&self) -> Platform {
+        self.platforms.keys().last().copied().unwrap_or(Platform::Chip8)
+    }
+
+    // Quirk preset of the platform, which only matters for the quirks the
+    // ROM depends on. A CHIP-8 ROM depending on none of them runs the same
+    // with any preset.
+    pub fn preset(&self) -> &'static str {
+        match self.platform() {
+            Platform::XoChip => "xochip",
+            Platform::Schip => "schip",
+            Platform::Chip8 if !self.shifts && !self.reuses_i && !self.jumps => "any",
+            Platform::Chip8 => "chip8",
+        }
+    }
+
+    // Setting of each quirk, as the platform's interpreters behave when the
+    // ROM depends on it
+    pub fn quirks(&self) -> Vec<(&'static str, &'static str)> {
+        let schip = self.platform() == Platform::Schip;
+        let setting = |found: bool, value: &'static str| if found { value } else { "any" };
+        vec![
+            ("shift", setting(self.shifts, if schip { "VX shifted in place" } else { "VY shifted into VX" })),
+            ("load/store", setting(self.reuses_i, if schip { "I left unchanged" } else { "I incremented" })),
+            ("jump", setting(self.jumps, if schip { "VX added" } else { "V0 added" })),
+        ]
+    }
+
+    fn warn(&mut self, address: u16, message: &str) {
+        self.warnings.push((address, message.to_string()));
+    }
+
+    // First instruction using I after the one at `address`, as long as no
+    // Annn sets it again in between
+    fn reuse_of_i(analysis: &Analysis, address: u16) -> Option<u16> {
+        let mut visited = BTreeSet::new();
+        let mut pending: Vec<(u16, usize)> = analysis.nodes[&address].successors.iter()
+            .filter(|(_, edge)| *edge != Edge::Call && *edge != Edge::Return)
+            .map(|&(target, _)| (target, 1))
+            .collect();
+
+        while let Some((current, depth)) = pending.pop() {
+            if depth > REUSE_DEPTH || !visited.insert(current) {
+                continue;
+            }
+            let node = match analysis.nodes.get(&current) {
+                Some(node) => node,
+                None => continue,
+            };
+            match node.instruction {
+                Some(Instruction::LoadI(_)) => continue,
+                Some(Instruction::Draw(..) | Instruction::Store(_) | Instruction::LoadRegs(_)
+                     | Instruction::StoreBcd(_) | Instruction::AddI(_)) => return Some(current),
+                _ => {}
+            }
+            pending.extend(node.successors.iter()
+                .filter(|(_, edge)| *edge != Edge::Call && *edge != Edge::Return)
+                .map(|&(target, _)| (target, depth + 1)));
+        }
+        None
+    }
+}
+
+// `lint` command: reports the instruction set used by the ROM and the
+// patterns whose behavior depends on the interpreter
+pub fn run(options: &Options) -> Result<(), Exception> {
+    let rom = fs::read(&options.rom).map_err(|_| Exception::new(FileError))?;
+    // Checked as when running, for the font range to fit in the memory
+    MemoryLayout::new(options.memory_layout, options.font_address.0, rom.len(), RAM_MAX as u16)?;
+    let analysis = Analysis::new(&rom)?;
+    let lint = Lint::new(&analysis, options.font_address.0);
+
+    println!("{}: {} bytes, {} instructions reached", options.rom, rom.len(), analysis.nodes.len());
+    println!("Instruction set: {}", lint.platform());
+    for (platform, opcodes) in &lint.platforms {
+        let examples: Vec<String> = opcodes.iter().take(4)
+            .map(|(address, opcode)| format!("{:04X} at {:#05X}", opcode, address))
+            .collect();
+        let more = if opcodes.len() > examples.len() { ", ..." } else { "" };
+        println!("  {}: {} opcodes ({}{})", platform, opcodes.len(), examples.join(", "), more);
+    }
+
+    if !lint.warnings.is_empty() {
+        println!("Warnings:");
+        for (address, message) in &lint.warnings {
+            println!("  {:#05X}: {}", address, message);
+        }
+    }
+    println!("Suggested quirk preset: {}", lint.preset());
+    for (quirk, setting) in lint.quirks() {
+        println!("  {}: {}", quirk, setting);
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use crate::analysis::Analysis;
+use crate::chip8::memory::layout::PROGRAM_START;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Region {
+    Code,
+    // Code bytes which are also read or overwritten through I
+    CodeRead,
+    CodeModified,
+    Data,
+    DataWritten,
+    Unreached,
+}
+
+impl Region {
+    fn name(&self) -> &'static str {
+        match self {
+            Region::Code => "code",
+            Region::CodeRead => "code, read as data",
+            Region::CodeModified => "code, self-modified",
+            Region::Data => "data",
+            Region::DataWritten => "data, written",
+            Region::Unreached => "unreached",
+        }
+    }
+}
+
+// What each byte of the ROM is used as, merged into ranges
+pub struct MemoryMap {
+    pub ranges: Vec<(u16, u16, Region)>,
+}
+
+impl MemoryMap {
+    pub fn new(analysis: &Analysis) -> MemoryMap {
+        let mut ranges: Vec<(u16, u16, Region)> = Vec::new();
+        for offset in 0..analysis.rom_size {
+            let address = PROGRAM_START + offset as u16;
+            let read = analysis.data.contains(&address);
+            let written = analysis.written.contains(&address);
+            let region = match (analysis.is_code(address), read, written) {
+                (true, _, true) => Region::CodeModified,
+                (true, true, false) => Region::CodeRead,
+                (true, false, false) => Region::Code,
+                (false, _, true) => Region::DataWritten,
+                (false, true, false) => Region::Data,
+                (false, false, false) => Region::Unreached,
+            };
+
+            match ranges.last_mut() {
+                Some((_, end, last)) if *last == region => *end = address,
+                _ => ranges.push((address, address, region)),
+            }
+        }
+        MemoryMap { ranges }
+    }
+
+    pub fn unreached(&self) -> usize {
+        self.ranges.iter()
+            .filter(|(_, _, region)| *region == Region::Unreached)
+            .map(|(start, end, _)| (end - start + 1) as usize)
+            .sum()
+    }
+}
+
+impl fmt::Display for MemoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (start, end, region) in &self.ranges {
+            writeln!(f, "{:#05X}-{:#05X} {:>5} bytes  {}", start, end, end - start + 1, region.name())?;
+        }
+        Ok(())
+    }
+}
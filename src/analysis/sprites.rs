@@ -0,0 +1,166 @@
+use std::path::Path;
+use std::str::FromStr;
+use crate::capture::write_png;
+use crate::chip8::memory::RAM_MAX;
+use crate::device::filter::Image;
+use crate::device::palette::{self, Palette};
+use crate::device::sprite::Sprite;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::BadArgument;
+use crate::new_chip8;
+use crate::options::Options;
+
+// Frames run to collect the drawn sprites when --frames isn't given
+const DEFAULT_FRAMES: u64 = 600;
+// Sprites per row, in the terminal and on the sheet
+const COLUMNS: usize = 8;
+
+// Size of the sprites cut from a memory range: 8xN like Dxyn draws them, or
+// 16x16 with 2 bytes per row like SCHIP's Dxy0
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpriteSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FromStr for SpriteSize {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<SpriteSize, Exception> {
+        match s.split_once('x') {
+            Some(("16", "16")) => Ok(SpriteSize { width: 16, height: 16 }),
+            Some(("8", height)) => height.parse().ok()
+                .filter(|height| (1..=15).contains(height))
+                .map(|height| SpriteSize { width: 8, height })
+                .ok_or(Exception::new(BadArgument)),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+struct Entry {
+    address: u16,
+    width: usize,
+    sprite: Sprite,
+    // Where it comes from, for the listing
+    note: String,
+}
+
+impl Entry {
+    fn height(&self) -> usize {
+        self.sprite.length().div_ceil(self.width / 8)
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        let byte = self.sprite.get(y * self.width / 8 + x / 8).unwrap_or(0);
+        byte & (0x80 >> (x % 8)) != 0
+    }
+}
+
+// `sprites` command: shows a memory range cut into sprites, or without a
+// range every sprite the ROM draws while running, and saves them as a PNG
+// sheet with --sheet
+pub fn run(options: &Options) -> Result<(), Exception> {
+    let mut chip8 = new_chip8(options)?;
+    let entries: Vec<Entry> = match options.sprite_range {
+        Some(range) => {
+            let size = options.sprite_size;
+            let length = size.width / 8 * size.height;
+            let addresses: Vec<u16> = (0..RAM_MAX as u16).filter(|&address| range.contains(address)).collect();
+            // A range which doesn't end on a whole sprite ends with a shorter one
+            addresses.chunks(length)
+                .map(|chunk| Entry {
+                    address: chunk[0],
+                    width: size.width,
+                    sprite: Sprite::new_with_content(chunk.iter().map(|&address| chip8.peek(address).unwrap_or(0)).collect()),
+                    note: if chunk.len() < length {
+                        format!(", partial: {} of {} bytes", chunk.len(), length)
+                    } else {
+                        String::new()
+                    },
+                })
+                .collect()
+        }
+        None => {
+            chip8.collect_sprites();
+            // Without any key pressed, games often stay on their title screen
+            for _ in 0..options.frames.unwrap_or(DEFAULT_FRAMES) {
+                if let Err(e) = chip8.run_frame() {
+                    println!("Stopped at frame {}: {}", chip8.frame(), e);
+                    break;
+                }
+            }
+            chip8.drawn_sprites().iter()
+                .map(|drawn| Entry {
+                    address: drawn.address,
+                    width: 8,
+                    sprite: Sprite::new_with_content(drawn.rows.clone()),
+                    note: format!(", first drawn in frame {}", drawn.first_frame),
+                })
+                .collect()
+        }
+    };
+
+    if entries.is_empty() {
+        println!("No sprites");
+        return Ok(());
+    }
+    for entry in &entries {
+        println!("  {:03X}  {}x{}{}", entry.address, entry.width, entry.height(), entry.note);
+    }
+    print_grid(&entries);
+
+    if let Some(path) = &options.sheet {
+        let (palettes, index) = palette::palettes(options)?;
+        write_png(Path::new(path), &sheet(&entries, &palettes[index]), options.sheet_scale)?;
+        println!("Sprite sheet saved to {}", path);
+    }
+    Ok(())
+}
+
+fn print_grid(entries: &[Entry]) {
+    let width = entries.iter().map(|entry| entry.width).max().unwrap_or(8).max(3);
+    for row in entries.chunks(COLUMNS) {
+        println!();
+        let labels: Vec<String> = row.iter().map(|entry| format!("{:<width$}", format!("{:03X}", entry.address))).collect();
+        println!("{}", labels.join("  ").trim_end());
+        let height = row.iter().map(Entry::height).max().unwrap_or(0);
+        for y in 0..height {
+            let lines: Vec<String> = row.iter()
+                .map(|entry| (0..width).map(|x| {
+                    if x >= entry.width || y >= entry.height() {
+                        ' '
+                    } else if entry.pixel(x, y) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                }).collect())
+                .collect();
+            println!("{}", lines.join("  ").trim_end());
+        }
+    }
+}
+
+// Grid of sprites in the palette's first two colors, separated by lines of
+// its third one
+fn sheet(entries: &[Entry], palette: &Palette) -> Image {
+    let cell_width = entries.iter().map(|entry| entry.width).max().unwrap_or(8) + 1;
+    let cell_height = entries.iter().map(Entry::height).max().unwrap_or(0) + 1;
+    let columns = entries.len().min(COLUMNS);
+    let rows = entries.len().div_ceil(COLUMNS);
+    let mut image = Image::new(columns * cell_width + 1, rows * cell_height + 1);
+    image.pixels.fill(palette.color(2));
+
+    for (index, entry) in entries.iter().enumerate() {
+        let left = (index % COLUMNS) * cell_width + 1;
+        let top = (index / COLUMNS) * cell_height + 1;
+        for y in 0..cell_height - 1 {
+            for x in 0..cell_width - 1 {
+                let lit = x < entry.width && y < entry.height() && entry.pixel(x, y);
+                image.pixels[(top + y) * image.width + left + x] = palette.color(lit as u8);
+            }
+        }
+    }
+    image
+}
@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use crate::capture::recorder::{RecordFormat, Recorder};
+use crate::device::filter::Image;
+use crate::device::framebuffer::Framebuffer;
+use crate::device::palette::Palette;
+use crate::device::speaker::ToneSettings;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::File as FileError;
+use crate::options::Options;
+
+pub(crate) mod recorder;
+pub(crate) mod wav;
+pub(crate) mod audio;
+
+pub struct Screenshots {
+    scale: u32,
+    directory: PathBuf,
+    rom_name: String,
+}
+
+impl Screenshots {
+    pub fn new(options: &Options) -> Screenshots {
+        Screenshots {
+            scale: options.screenshot_scale,
+            directory: PathBuf::from(&options.capture_dir),
+            rom_name: rom_name(&options.rom),
+        }
+    }
+
+    pub fn save(&self, framebuffer: &Framebuffer, palette: &Palette, frame: u64) -> Result<PathBuf, Exception> {
+        let path = next_path(&self.directory, &format!("{}-{:06}", self.rom_name, frame), "png");
+        write_png(&path, &Image::from_framebuffer(framebuffer, palette), self.scale)?;
+        Ok(path)
+    }
+}
+
+// Recordings started from a hotkey, named after the ROM and the frame they
+// start at
+pub struct Recordings {
+    scale: u32,
+    format: RecordFormat,
+    tone: ToneSettings,
+    directory: PathBuf,
+    rom_name: String,
+}
+
+impl Recordings {
+    pub fn new(options: &Options) -> Recordings {
+        Recordings {
+            scale: options.record_scale,
+            format: options.record_format,
+            tone: options.tone,
+            directory: PathBuf::from(&options.capture_dir),
+            rom_name: rom_name(&options.rom),
+        }
+    }
+
+    pub fn start(&self, frame: u64) -> Result<Recorder, Exception> {
+        let path = next_path(&self.directory, &format!("{}-{:06}", self.rom_name, frame), self.format.extension());
+        Recorder::start(&path, self.scale, self.tone, None)
+    }
+}
+
+// File stem of the ROM, used to name the captures
+pub fn rom_name(rom: &str) -> String {
+    Path::new(rom).file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or(String::from("chip8"))
+}
+
+// Returns `directory/name.extension`, or `directory/name-N.extension` with the
+// first N not already taken
+pub fn next_path(directory: &Path, name: &str, extension: &str) -> PathBuf {
+    let mut path = directory.join(format!("{}.{}", name, extension));
+    let mut index = 1;
+    while path.exists() {
+        path = directory.join(format!("{}-{}.{}", name, index, extension));
+        index += 1;
+    }
+    path
+}
+
+pub fn write_png(path: &Path, image: &Image, scale: u32) -> Result<(), Exception> {
+    let scale = scale.max(1) as usize;
+    let (width, height) = (image.width * scale, image.height * scale);
+    let mut data = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let color = image.pixels[(y / scale) * image.width + x / scale];
+            data.extend_from_slice(&[color.0, color.1, color.2]);
+        }
+    }
+
+    let file = File::create(path).map_err(|_| Exception::new(FileError))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|_| Exception::new(FileError))?;
+    writer.write_image_data(&data).map_err(|_| Exception::new(FileError))
+}
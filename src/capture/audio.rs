@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+use crate::capture::wav::WavWriter;
+use crate::device::speaker::{Tone, ToneSettings};
+use crate::exceptions::Exception;
+
+// Renders the buzzer gate of each frame to a WAV file, without any audio
+// device, at any sample rate
+pub struct WavExport {
+    path: PathBuf,
+    tone: Tone,
+    wav: WavWriter,
+    sample_rate: u64,
+    frames: u64,
+}
+
+impl WavExport {
+    pub fn create(path: &Path, sample_rate: u32, tone: ToneSettings) -> Result<WavExport, Exception> {
+        Ok(WavExport {
+            path: path.to_path_buf(),
+            tone: Tone::new(tone, sample_rate as i32),
+            wav: WavWriter::create(path, sample_rate)?,
+            sample_rate: sample_rate as u64,
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn configure(&mut self, volume: f32, muted: bool) {
+        self.tone.set_volume(volume);
+        self.tone.muted = muted;
+    }
+
+    pub fn add_frame(&mut self, gate: bool) -> Result<(), Exception> {
+        // Frames don't always last a whole number of samples, the total
+        // written is kept in step with the emulated time instead, and the gate
+        // lasts exactly the samples written for its frame
+        let start = (self.frames * self.sample_rate + 30) / 60;
+        let end = ((self.frames + 1) * self.sample_rate + 30) / 60;
+        let mut samples = vec![0.0; (end - start) as usize];
+
+        self.tone.gate = gate;
+        self.tone.fill(&mut samples);
+        self.wav.write(&samples)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), Exception> {
+        self.wav.finish()
+    }
+}
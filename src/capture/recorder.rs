@@ -0,0 +1,209 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::capture::audio::WavExport;
+use crate::device::filter::Image;
+use crate::device::palette::Rgb;
+use crate::device::speaker::ToneSettings;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, File as FileError};
+
+const SAMPLE_RATE: u32 = 44100;
+// Only every other frame goes into GIFs: viewers slow down delays under 2
+// hundredths of seconds, which 60 fps would need
+const GIF_FRAME_STEP: u64 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordFormat {
+    // Animated GIF, palette-indexed
+    Gif,
+    // Raw YUV 4:4:4 stream, readable by ffmpeg
+    Y4m,
+    // Concatenated binary PPM images, for `ffmpeg -f image2pipe -c:v ppm`
+    Ppm,
+}
+
+impl FromStr for RecordFormat {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<RecordFormat, Exception> {
+        match s {
+            "gif" => Ok(RecordFormat::Gif),
+            "y4m" => Ok(RecordFormat::Y4m),
+            "ppm" => Ok(RecordFormat::Ppm),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+impl RecordFormat {
+    pub fn from_path(path: &Path) -> Result<RecordFormat, Exception> {
+        path.extension().and_then(|extension| extension.to_str())
+            .ok_or(Exception::new(BadArgument))?
+            .parse()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "gif",
+            RecordFormat::Y4m => "y4m",
+            RecordFormat::Ppm => "ppm",
+        }
+    }
+}
+
+enum VideoWriter {
+    Gif(gif::Encoder<BufWriter<File>>),
+    Stream(BufWriter<File>),
+}
+
+// Records the presented frames to a video file and the buzzer to a WAV file
+// next to it. The video size is fixed by the first frame.
+pub struct Recorder {
+    path: PathBuf,
+    format: RecordFormat,
+    scale: usize,
+    video: Option<(VideoWriter, usize, usize)>,
+    audio: WavExport,
+    frames: u64,
+    limit: Option<u64>,
+}
+
+impl Recorder {
+    pub fn start(path: &Path, scale: u32, tone: ToneSettings, limit: Option<u64>) -> Result<Recorder, Exception> {
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            format: RecordFormat::from_path(path)?,
+            scale: scale.max(1) as usize,
+            video: None,
+            audio: WavExport::create(&path.with_extension("wav"), SAMPLE_RATE, tone)?,
+            frames: 0,
+            limit,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.limit.is_some_and(|limit| self.frames >= limit)
+    }
+
+    pub fn add_frame(&mut self, image: &Image, buzzing: bool) -> Result<(), Exception> {
+        if self.video.is_none() {
+            let (width, height) = (image.width * self.scale, image.height * self.scale);
+            self.video = Some((self.open(width, height)?, width, height));
+        }
+        let Some((video, width, height)) = &mut self.video else {
+            return Ok(());
+        };
+        let pixels = resize(image, *width, *height);
+
+        match video {
+            VideoWriter::Gif(encoder) if self.frames.is_multiple_of(GIF_FRAME_STEP) => {
+                let mut frame = gif_frame(*width as u16, *height as u16, &pixels);
+                // Delays are in hundredths of seconds, spread them to average 30 fps
+                let next = self.frames + GIF_FRAME_STEP;
+                frame.delay = ((next * 100 + 30) / 60 - (self.frames * 100 + 30) / 60) as u16;
+                encoder.write_frame(&frame).map_err(|_| Exception::new(FileError))?;
+            }
+            VideoWriter::Gif(_) => {}
+            VideoWriter::Stream(writer) => {
+                let data = match self.format {
+                    RecordFormat::Y4m => y4m_frame(&pixels),
+                    _ => ppm_frame(*width, *height, &pixels),
+                };
+                writer.write_all(&data).map_err(|_| Exception::new(FileError))?;
+            }
+        }
+
+        self.audio.add_frame(buzzing)?;
+
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), Exception> {
+        if let Some((VideoWriter::Stream(mut writer), _, _)) = self.video {
+            writer.flush().map_err(|_| Exception::new(FileError))?;
+        }
+        self.audio.finish()
+    }
+
+    fn open(&self, width: usize, height: usize) -> Result<VideoWriter, Exception> {
+        let file = BufWriter::new(File::create(&self.path).map_err(|_| Exception::new(FileError))?);
+        match self.format {
+            RecordFormat::Gif => {
+                let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])
+                    .map_err(|_| Exception::new(FileError))?;
+                encoder.set_repeat(gif::Repeat::Infinite).map_err(|_| Exception::new(FileError))?;
+                Ok(VideoWriter::Gif(encoder))
+            }
+            RecordFormat::Y4m => {
+                let mut writer = file;
+                writeln!(writer, "YUV4MPEG2 W{} H{} F60:1 Ip A1:1 C444", width, height)
+                    .map_err(|_| Exception::new(FileError))?;
+                Ok(VideoWriter::Stream(writer))
+            }
+            RecordFormat::Ppm => Ok(VideoWriter::Stream(file)),
+        }
+    }
+}
+
+// Nearest neighbour scaling of the image to the recording size
+fn resize(image: &Image, width: usize, height: usize) -> Vec<Rgb> {
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            pixels.push(image.pixels[(y * image.height / height) * image.width + x * image.width / width]);
+        }
+    }
+    pixels
+}
+
+// Frames with at most 256 colors, which is always the case without the CRT
+// filter, are indexed exactly, the others are quantized
+fn gif_frame(width: u16, height: u16, pixels: &[Rgb]) -> gif::Frame<'static> {
+    let mut colors: Vec<Rgb> = Vec::new();
+    let mut indices = Vec::with_capacity(pixels.len());
+    for pixel in pixels {
+        let index = match colors.iter().position(|color| color == pixel) {
+            Some(index) => index,
+            None => {
+                colors.push(*pixel);
+                colors.len() - 1
+            }
+        };
+        if index > 255 {
+            let rgb: Vec<u8> = pixels.iter().flat_map(|pixel| [pixel.0, pixel.1, pixel.2]).collect();
+            return gif::Frame::from_rgb_speed(width, height, &rgb, 10);
+        }
+        indices.push(index as u8);
+    }
+    let palette: Vec<u8> = colors.iter().flat_map(|color| [color.0, color.1, color.2]).collect();
+    gif::Frame::from_palette_pixels(width, height, indices, palette, None)
+}
+
+fn ppm_frame(width: usize, height: usize, pixels: &[Rgb]) -> Vec<u8> {
+    let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    data.extend(pixels.iter().flat_map(|pixel| [pixel.0, pixel.1, pixel.2]));
+    data
+}
+
+// BT.601 conversion to planar Y, Cb, Cr
+fn y4m_frame(pixels: &[Rgb]) -> Vec<u8> {
+    let mut data = b"FRAME\n".to_vec();
+    let planes: [fn(f32, f32, f32) -> f32; 3] = [
+        |r, g, b| 16.0 + 0.257 * r + 0.504 * g + 0.098 * b,
+        |r, g, b| 128.0 - 0.148 * r - 0.291 * g + 0.439 * b,
+        |r, g, b| 128.0 + 0.439 * r - 0.368 * g - 0.071 * b,
+    ];
+    for plane in planes {
+        data.extend(pixels.iter().map(|pixel| {
+            plane(pixel.0 as f32, pixel.1 as f32, pixel.2 as f32).round().clamp(0.0, 255.0) as u8
+        }));
+    }
+    data
+}
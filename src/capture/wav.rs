@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::File as FileError;
+
+// Mono 16-bit PCM WAV file. The sizes in the header are only known once every
+// sample has been written, so they are patched in `finish`.
+pub struct WavWriter {
+    writer: BufWriter<File>,
+    samples: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32) -> Result<WavWriter, Exception> {
+        let file = File::create(path).map_err(|_| Exception::new(FileError))?;
+        let mut wav = WavWriter {
+            writer: BufWriter::new(file),
+            samples: 0,
+        };
+
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&36u32.to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        header.extend_from_slice(&1u16.to_le_bytes()); // Channels
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // Bytes per second
+        header.extend_from_slice(&2u16.to_le_bytes()); // Bytes per sample
+        header.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        wav.writer.write_all(&header).map_err(|_| Exception::new(FileError))?;
+
+        Ok(wav)
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), Exception> {
+        let data: Vec<u8> = samples.iter()
+            .flat_map(|sample| ((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+            .collect();
+        self.writer.write_all(&data).map_err(|_| Exception::new(FileError))?;
+        self.samples += samples.len() as u32;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Exception> {
+        let patch = |writer: &mut BufWriter<File>, offset: u64, value: u32| {
+            writer.seek(SeekFrom::Start(offset))?;
+            writer.write_all(&value.to_le_bytes())
+        };
+        patch(&mut self.writer, 4, 36 + self.samples * 2)
+            .and_then(|_| patch(&mut self.writer, 40, self.samples * 2))
+            .and_then(|_| self.writer.flush())
+            .map_err(|_| Exception::new(FileError))
+    }
+}
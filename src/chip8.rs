@@ -1,122 +1,186 @@
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell, RefMut};
 use std::rc::Rc;
-use std::fs;
-use std::time::{Duration, Instant};
-use crate::chip8::memory::RandomAccessMemory;
+use crate::chip8::coverage::Coverage;
+use crate::chip8::font::{DEFAULT_FONT_ADDRESS, Font, FontSet};
+use crate::chip8::memory::{OutOfRange, RAM_MAX, RandomAccessMemory, ReservedWrites};
+use crate::chip8::memory::layout::{Layout, MemoryLayout, PROGRAM_START};
 use crate::chip8::processor::Processor;
-use crate::device::display::Display;
+use crate::chip8::profiler::Profiler;
+use crate::chip8::sprites::{DrawnSprite, SpriteCollector};
+use crate::chip8::stack::CallStack;
+use crate::chip8::tracer::{CpuState, Tracer};
+use crate::device::framebuffer::Framebuffer;
 use crate::device::keyboard::Keyboard;
-use crate::device::speaker::Speaker;
 use crate::exceptions::Exception;
-use crate::exceptions::ExceptionType::{SDL};
 
-mod memory;
+pub mod instruction;
+pub mod coverage;
+pub mod font;
+pub mod memory;
 mod processor;
+pub mod profiler;
+pub mod sprites;
+pub mod stack;
+pub mod tracer;
 
+const INSTRUCTIONS_PER_FRAME: u32 = 11;
+
+// The emulated machine, independent of any frontend: frontends feed the
+// keyboard, call `run_frame` 60 times per second and present the framebuffer.
 pub struct Chip8 {
     processor: Processor,
     ram: Rc<RefCell<RandomAccessMemory>>,
-    display: Rc<RefCell<Display>>,
-    speaker: Rc<RefCell<Speaker>>,
+    framebuffer: Rc<RefCell<Framebuffer>>,
     keyboard: Rc<RefCell<Keyboard>>,
-
-    sdl_context: sdl2::Sdl,
+    frame: u64,
+    // Instructions run since the start of the frame
+    steps: u32,
 }
 
 impl Chip8 {
-    pub fn new(rom_content: &str) -> Result<Chip8, Exception> {
-        let sdl_context = sdl2::init().map_err(|_| Exception::new(SDL))?;
-        let ram = Rc::new(RefCell::new(RandomAccessMemory::new()));
-        let display = Rc::new(RefCell::new(Display::new(&sdl_context.video().unwrap(), sdl_context.timer().unwrap())));
+    pub fn new(rom: &[u8], font: &Font, memory_layout: Layout, reserved_writes: ReservedWrites,
+               out_of_range: OutOfRange) -> Result<Chip8, Exception> {
+        let layout = MemoryLayout::new(memory_layout, font.address, rom.len(), RAM_MAX as u16)?;
+        Chip8::with_memory(rom, RandomAccessMemory::new(layout, reserved_writes, out_of_range), font)
+    }
+
+    // Machine without any of the debugging tools, as the fuzz targets use it
+    pub fn from_rom(rom: &[u8]) -> Result<Chip8, Exception> {
+        let font = Font::new(FontSet::Schip, DEFAULT_FONT_ADDRESS);
+        Chip8::new(rom, &font, Layout::Modern, ReservedWrites::Allow, OutOfRange::Error)
+    }
+
+    // The debugging tools, attached before running
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.processor.stack = CallStack::new(depth);
+    }
+
+    pub fn profile(&mut self) {
+        self.processor.profiler = Some(Profiler::new(PROGRAM_START));
+    }
+
+    pub fn trace(&mut self, tracer: Tracer) {
+        self.processor.tracer = Some(tracer);
+    }
+
+    pub fn cover(&mut self, rom: &[u8]) {
+        self.processor.coverage = Some(Coverage::new(rom));
+    }
+
+    fn with_memory(rom: &[u8], ram: RandomAccessMemory, font: &Font) -> Result<Chip8, Exception> {
+        let ram = Rc::new(RefCell::new(ram));
+        let framebuffer = Rc::new(RefCell::new(Framebuffer::new()));
         let keyboard = Rc::new(RefCell::new(Keyboard::new()));
-        let speaker = Rc::new(RefCell::new(Speaker::new(&sdl_context.audio().unwrap())));
 
         let mut processor = Processor::new(
             Rc::clone(&ram),
-            Rc::clone(&display),
+            Rc::clone(&framebuffer),
             Rc::clone(&keyboard));
-        processor.load_sprites()?;
+        processor.load_font(font)?;
 
-        let mut c8 = Chip8 {
+        let c8 = Chip8 {
             processor,
             ram,
-            display,
-            speaker,
+            framebuffer,
             keyboard,
-            sdl_context,
+            frame: 0,
+            steps: 0,
         };
-        c8.load_rom(&*c8.read_rom(rom_content)?)?;
-
+        c8.ram.borrow_mut().load(PROGRAM_START, rom)?;
         Ok(c8)
     }
 
-    pub(crate) fn start(&mut self) -> Result<(), Exception> {
-        self.cycle()?;
-        Ok(())
+    pub fn framebuffer(&self) -> Ref<'_, Framebuffer> {
+        self.framebuffer.borrow()
+    }
+
+    // Number of frames run since the start
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // Hotspots report, when profiling
+    pub fn profile_report(&self) -> Option<String> {
+        self.processor.profiler.as_ref().map(|profiler| profiler.report(self.frame))
+    }
+
+    pub fn call_stack(&self) -> &CallStack {
+        &self.processor.stack
+    }
+
+    // Starts keeping the sprites drawn from now on
+    pub fn collect_sprites(&mut self) {
+        self.processor.sprites = Some(SpriteCollector::new());
     }
 
-    fn read_rom(&self, rom_path: &str) -> Result<Vec<u8>, Exception> {
-        fs::read(rom_path).map_err(|_| Exception::new(SDL))
+    pub fn drawn_sprites(&self) -> &[DrawnSprite] {
+        self.processor.sprites.as_ref().map_or(&[], |sprites| &sprites.sprites)
     }
 
-    fn load_rom(&mut self, rom_content: &[u8]) -> Result<(), Exception> {
-        // Write the file content to the memory
-        let mut address = 512;
-        for &byte in rom_content {
-            self.ram.borrow_mut().write(address, byte)?;
-            address += 1;
+    pub fn coverage_mut(&mut self) -> Option<&mut Coverage> {
+        self.processor.coverage.as_mut()
+    }
+
+    pub fn keyboard(&self) -> RefMut<'_, Keyboard> {
+        self.keyboard.borrow_mut()
+    }
+
+    // Runs the instructions of one frame then ticks the timers as on a
+    // vertical blank. Returns whether the buzzer sounds during this frame.
+    pub fn run_frame(&mut self) -> Result<bool, Exception> {
+        while self.steps < INSTRUCTIONS_PER_FRAME {
+            self.processor.fetch_decode_execute()?;
+            self.steps += 1;
         }
+        Ok(self.end_frame())
+    }
 
+    // Runs a single instruction, ending the frame after its last one
+    pub fn step(&mut self) -> Result<(), Exception> {
+        self.processor.fetch_decode_execute()?;
+        self.steps += 1;
+        if self.steps == INSTRUCTIONS_PER_FRAME {
+            self.end_frame();
+        }
         Ok(())
     }
 
-    fn cycle(&mut self) -> Result<(), Exception> {
-        let mut event_pump = self.sdl_context.event_pump().map_err(|_| Exception::new(SDL))?;
-        let mut cpt = 0;
-        let mut time: Instant;
-        let mut last_time = Instant::now();
-
-        loop {
-            for event in event_pump.poll_event() {
-                match event {
-                    sdl2::event::Event::Quit { .. } => {
-                        println!("Quitting");
-                        return Ok(());
-                    }
-                    _ => {
-                        self.keyboard.borrow_mut().handle_event(event);
-                    }
-                }
-            }
-            time = Instant::now();
-
-            if time - last_time >= Duration::from_millis(1000 / 60) {
-                if self.processor.dt > 0 {
-                    self.processor.dt -= 1;
-                }
-
-                if self.processor.st > 0 {
-                    self.processor.st -= 1;
-                    self.speaker.borrow_mut().on();
-                } else {
-                    self.speaker.borrow_mut().off();
-                }
-
-                last_time = time;
-            }
+    pub fn state(&self) -> CpuState {
+        self.processor.state()
+    }
 
-            self.processor.fetch_decode_execute()?;
+    pub fn set_state(&mut self, state: &CpuState) {
+        self.processor.set_state(state);
+    }
+
+    // Memory byte, None past the end of the RAM
+    pub fn peek(&self, address: u16) -> Option<u8> {
+        self.ram.borrow().read(address).ok()
+    }
+
+    // Changes a byte whatever the region, as a debugger does
+    pub fn poke(&mut self, address: u16, value: u8) -> Result<(), Exception> {
+        self.ram.borrow_mut().load(address, &[value])
+    }
 
-            if cpt % 2 == 0 {
-                self.display.borrow_mut().update()?;
-            }
+    fn end_frame(&mut self) -> bool {
+        self.steps = 0;
+        self.frame += 1;
+        if let Some(tracer) = &mut self.processor.tracer {
+            tracer.frame = self.frame;
+        }
+        if let Some(sprites) = &mut self.processor.sprites {
+            sprites.frame = self.frame;
+        }
+        if self.processor.dt > 0 {
+            self.processor.dt -= 1;
+        }
 
-            let frame_time = Duration::from_millis(1000 / 60);
-            let elapsed = last_time.elapsed();
-            if elapsed < frame_time {
-                std::thread::sleep(frame_time - elapsed);
-            }
-            cpt += 1;
+        if self.processor.st > 0 {
+            self.processor.st -= 1;
+            true
+        } else {
+            false
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use crate::chip8::instruction::Instruction;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::File as FileError;
+
+// Addresses executed and read as data during one or several runs of a ROM.
+// It is saved as lines of `kind address counts...`, so that the runs add up:
+//   x 200 12      instruction executed 12 times
+//   b 208 3 9     skip taken 3 times, not taken 9 times
+//   d 300 4       byte read 4 times by a sprite or Fx65
+pub struct Coverage {
+    pub executed: BTreeMap<u16, u64>,
+    pub branches: BTreeMap<u16, (u64, u64)>,
+    pub data: BTreeMap<u16, u64>,
+    // Identifies the ROM the counts belong to
+    rom: String,
+}
+
+impl Coverage {
+    pub fn new(rom: &[u8]) -> Coverage {
+        let checksum = rom.iter().fold(0u32, |sum, &byte| sum.rotate_left(5) ^ byte as u32);
+        Coverage {
+            executed: BTreeMap::new(),
+            branches: BTreeMap::new(),
+            data: BTreeMap::new(),
+            rom: format!("{} {:08x}", rom.len(), checksum),
+        }
+    }
+
+    pub fn record(&mut self, address: u16, instruction: Instruction, i: u16) {
+        *self.executed.entry(address).or_insert(0) += 1;
+        let read = match instruction {
+            Instruction::Draw(_, _, n) => n as u16,
+            Instruction::LoadRegs(x) => x as u16 + 1,
+            _ => 0,
+        };
+        for offset in 0..read {
+            *self.data.entry(i.wrapping_add(offset)).or_insert(0) += 1;
+        }
+    }
+
+    pub fn record_branch(&mut self, address: u16, taken: bool) {
+        let counts = self.branches.entry(address).or_insert((0, 0));
+        if taken {
+            counts.0 += 1;
+        } else {
+            counts.1 += 1;
+        }
+    }
+
+    // Adds the counts saved by the previous runs, unless they were made with
+    // another ROM
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), Exception> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(path).map_err(|_| Exception::new(FileError))?;
+        let mut lines = content.lines();
+        if lines.next().and_then(|line| line.strip_prefix("rom ")) != Some(self.rom.as_str()) {
+            eprintln!("Warning: {} was made with another ROM, it is overwritten", path.display());
+            return Ok(());
+        }
+
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |index: usize| fields.get(index).and_then(|field| field.parse::<u64>().ok());
+            let address = fields.get(1).and_then(|field| u16::from_str_radix(field, 16).ok());
+            match (fields.first(), address) {
+                (Some(&"x"), Some(address)) => {
+                    *self.executed.entry(address).or_insert(0) += number(2).unwrap_or(0);
+                }
+                (Some(&"b"), Some(address)) => {
+                    let counts = self.branches.entry(address).or_insert((0, 0));
+                    counts.0 += number(2).unwrap_or(0);
+                    counts.1 += number(3).unwrap_or(0);
+                }
+                (Some(&"d"), Some(address)) => {
+                    *self.data.entry(address).or_insert(0) += number(2).unwrap_or(0);
+                }
+                _ => return Err(Exception::new(FileError)),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Exception> {
+        let mut content = format!("rom {}\n", self.rom);
+        for (address, count) in &self.executed {
+            writeln!(content, "x {:03x} {}", address, count).unwrap();
+        }
+        for (address, (taken, not_taken)) in &self.branches {
+            writeln!(content, "b {:03x} {} {}", address, taken, not_taken).unwrap();
+        }
+        for (address, count) in &self.data {
+            writeln!(content, "d {:03x} {}", address, count).unwrap();
+        }
+        fs::write(path, content).map_err(|_| Exception::new(FileError))
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::str::FromStr;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, File as FileError};
+
+// 16 hexadecimal digits of 5 rows each
+pub const FONT_SIZE: u16 = 16 * 5;
+pub const DEFAULT_FONT_ADDRESS: u16 = 431;
+
+const VIP: [[u8; 5]; 16] = [
+    [0xF0, 0x90, 0x90, 0x90, 0xF0], [0x60, 0x20, 0x20, 0x20, 0x70],
+    [0xF0, 0x10, 0xF0, 0x80, 0xF0], [0xF0, 0x10, 0xF0, 0x10, 0xF0],
+    [0xA0, 0xA0, 0xF0, 0x20, 0x20], [0xF0, 0x80, 0xF0, 0x10, 0xF0],
+    [0xF0, 0x80, 0xF0, 0x90, 0xF0], [0xF0, 0x10, 0x10, 0x10, 0x10],
+    [0xF0, 0x90, 0xF0, 0x90, 0xF0], [0xF0, 0x90, 0xF0, 0x10, 0xF0],
+    [0xF0, 0x90, 0xF0, 0x90, 0x90], [0xF0, 0x50, 0x70, 0x50, 0xF0],
+    [0xF0, 0x80, 0x80, 0x80, 0xF0], [0xF0, 0x50, 0x50, 0x50, 0xF0],
+    [0xF0, 0x80, 0xF0, 0x80, 0xF0], [0xF0, 0x80, 0xF0, 0x80, 0x80],
+];
+
+const DREAM_6800: [[u8; 5]; 16] = [
+    [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0xE0, 0x20, 0xE0, 0x80, 0xE0], [0xE0, 0x20, 0xE0, 0x20, 0xE0],
+    [0x80, 0xA0, 0xA0, 0xE0, 0x20], [0xE0, 0x80, 0xE0, 0x20, 0xE0],
+    [0xE0, 0x80, 0xE0, 0xA0, 0xE0], [0xE0, 0x20, 0x20, 0x20, 0x20],
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], [0xE0, 0xA0, 0xE0, 0x20, 0xE0],
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], [0xC0, 0xA0, 0xE0, 0xA0, 0xC0],
+    [0xE0, 0x80, 0x80, 0x80, 0xE0], [0xC0, 0xA0, 0xA0, 0xA0, 0xC0],
+    [0xE0, 0x80, 0xE0, 0x80, 0xE0], [0xE0, 0x80, 0xC0, 0x80, 0x80],
+];
+
+const ETI_660: [[u8; 5]; 16] = [
+    [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], [0x20, 0x20, 0x20, 0x20, 0x20],
+    [0xE0, 0x20, 0xE0, 0x80, 0xE0], [0xE0, 0x20, 0xE0, 0x20, 0xE0],
+    [0xA0, 0xA0, 0xE0, 0x20, 0x20], [0xE0, 0x80, 0xE0, 0x20, 0xE0],
+    [0xE0, 0x80, 0xE0, 0xA0, 0xE0], [0xE0, 0x20, 0x20, 0x20, 0x20],
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], [0xE0, 0xA0, 0xE0, 0x20, 0xE0],
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], [0x80, 0x80, 0xE0, 0xA0, 0xE0],
+    [0xE0, 0x80, 0x80, 0x80, 0xE0], [0x20, 0x20, 0xE0, 0xA0, 0xE0],
+    [0xE0, 0x80, 0xE0, 0x80, 0xE0], [0xE0, 0x80, 0xC0, 0x80, 0x80],
+];
+
+const SCHIP: [[u8; 5]; 16] = [
+    [0xF0, 0x90, 0x90, 0x90, 0xF0], [0x20, 0x60, 0x20, 0x20, 0x70],
+    [0xF0, 0x10, 0xF0, 0x80, 0xF0], [0xF0, 0x10, 0xF0, 0x10, 0xF0],
+    [0x90, 0x90, 0xF0, 0x10, 0x10], [0xF0, 0x80, 0xF0, 0x10, 0xF0],
+    [0xF0, 0x80, 0xF0, 0x90, 0xF0], [0xF0, 0x10, 0x20, 0x40, 0x40],
+    [0xF0, 0x90, 0xF0, 0x90, 0xF0], [0xF0, 0x90, 0xF0, 0x10, 0xF0],
+    [0xF0, 0x90, 0xF0, 0x90, 0x90], [0xE0, 0x90, 0xE0, 0x90, 0xE0],
+    [0xF0, 0x80, 0x80, 0x80, 0xF0], [0xE0, 0x90, 0x90, 0x90, 0xE0],
+    [0xF0, 0x80, 0xF0, 0x80, 0xF0], [0xF0, 0x80, 0xF0, 0x80, 0x80],
+];
+
+// Small fonts of the interpreters, as they were in their ROMs
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FontSet {
+    Vip,
+    Dream6800,
+    Eti660,
+    Schip,
+}
+
+impl FromStr for FontSet {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<FontSet, Exception> {
+        match s {
+            "vip" => Ok(FontSet::Vip),
+            "dream6800" => Ok(FontSet::Dream6800),
+            "eti660" => Ok(FontSet::Eti660),
+            "schip" => Ok(FontSet::Schip),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+pub struct Font {
+    pub glyphs: Vec<u8>,
+    pub address: u16,
+}
+
+impl Font {
+    pub fn new(set: FontSet, address: u16) -> Font {
+        let glyphs = match set {
+            FontSet::Vip => &VIP,
+            FontSet::Dream6800 => &DREAM_6800,
+            FontSet::Eti660 => &ETI_660,
+            FontSet::Schip => &SCHIP,
+        };
+        Font { glyphs: glyphs.as_flattened().to_vec(), address }
+    }
+
+    // The file holds the 80 bytes of the font, digit 0 first
+    pub fn load(path: &str, address: u16) -> Result<Font, Exception> {
+        let glyphs = fs::read(path).map_err(|_| Exception::new(FileError))?;
+        if glyphs.len() != FONT_SIZE as usize {
+            return Err(Exception::new(BadArgument));
+        }
+        Ok(Font { glyphs, address })
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+
+// Machine an opcode was introduced on. Only CHIP-8 opcodes are decoded, the
+// others are recognised to tell which platform a ROM targets.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Platform {
+    Chip8,
+    Schip,
+    XoChip,
+}
+
+impl Platform {
+    pub fn of(opcode: u16) -> Platform {
+        match opcode {
+            0x00D0..=0x00DF | 0xF000 | 0xF002 => Platform::XoChip,
+            0x00C0..=0x00CF | 0x00FB..=0x00FF => Platform::Schip,
+            _ => match opcode & 0xF00F {
+                0x5002 | 0x5003 => Platform::XoChip,
+                0xD000 => Platform::Schip,
+                _ => match opcode & 0xF0FF {
+                    0xF001 | 0xF03A => Platform::XoChip,
+                    0xF030 | 0xF075 | 0xF085 => Platform::Schip,
+                    _ => Platform::Chip8,
+                },
+            },
+        }
+    }
+
+    // Length of the opcode in bytes, F000 being followed by a 16-bit address
+    pub fn length(opcode: u16) -> u16 {
+        if opcode == 0xF000 { 4 } else { 2 }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Platform::Chip8 => write!(f, "CHIP-8"),
+            Platform::Schip => write!(f, "SCHIP"),
+            Platform::XoChip => write!(f, "XO-CHIP"),
+        }
+    }
+}
+
+// Decoded opcode, shared by the processor and the ROM analysis tools
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Instruction {
+    Sys(u16),
+    Cls,
+    Ret,
+    Jump(u16),
+    Call(u16),
+    SkipEq(u8, u8),
+    SkipNe(u8, u8),
+    SkipEqReg(u8, u8),
+    Load(u8, u8),
+    Add(u8, u8),
+    Move(u8, u8),
+    Or(u8, u8),
+    And(u8, u8),
+    Xor(u8, u8),
+    AddReg(u8, u8),
+    Sub(u8, u8),
+    ShiftRight(u8, u8),
+    SubN(u8, u8),
+    ShiftLeft(u8, u8),
+    SkipNeReg(u8, u8),
+    LoadI(u16),
+    JumpV0(u16),
+    Random(u8, u8),
+    Draw(u8, u8, u8),
+    SkipKey(u8),
+    SkipNoKey(u8),
+    LoadDelay(u8),
+    WaitKey(u8),
+    SetDelay(u8),
+    SetSound(u8),
+    AddI(u8),
+    LoadFont(u8),
+    StoreBcd(u8),
+    Store(u8),
+    LoadRegs(u8),
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Option<Instruction> {
+        let nnn = opcode & 0x0FFF;
+        let x = ((opcode & 0x0F00) >> 8) as u8;
+        let y = ((opcode & 0x00F0) >> 4) as u8;
+        let kk = (opcode & 0x00FF) as u8;
+        let n = (opcode & 0x000F) as u8;
+
+        let instruction = match (opcode & 0xF000) >> 12 {
+            0x0 => match opcode {
+                0x00E0 => Instruction::Cls,
+                0x00EE => Instruction::Ret,
+                _ => Instruction::Sys(nnn),
+            },
+            0x1 => Instruction::Jump(nnn),
+            0x2 => Instruction::Call(nnn),
+            0x3 => Instruction::SkipEq(x, kk),
+            0x4 => Instruction::SkipNe(x, kk),
+            0x5 => Instruction::SkipEqReg(x, y),
+            0x6 => Instruction::Load(x, kk),
+            0x7 => Instruction::Add(x, kk),
+            0x8 => match n {
+                0x0 => Instruction::Move(x, y),
+                0x1 => Instruction::Or(x, y),
+                0x2 => Instruction::And(x, y),
+                0x3 => Instruction::Xor(x, y),
+                0x4 => Instruction::AddReg(x, y),
+                0x5 => Instruction::Sub(x, y),
+                0x6 => Instruction::ShiftRight(x, y),
+                0x7 => Instruction::SubN(x, y),
+                0xE => Instruction::ShiftLeft(x, y),
+                _ => return None,
+            },
+            0x9 => Instruction::SkipNeReg(x, y),
+            0xA => Instruction::LoadI(nnn),
+            0xB => Instruction::JumpV0(nnn),
+            0xC => Instruction::Random(x, kk),
+            0xD => Instruction::Draw(x, y, n),
+            0xE => match kk {
+                0x9E => Instruction::SkipKey(x),
+                0xA1 => Instruction::SkipNoKey(x),
+                _ => return None,
+            },
+            _ => match kk {
+                0x07 => Instruction::LoadDelay(x),
+                0x0A => Instruction::WaitKey(x),
+                0x15 => Instruction::SetDelay(x),
+                0x18 => Instruction::SetSound(x),
+                0x1E => Instruction::AddI(x),
+                0x29 => Instruction::LoadFont(x),
+                0x33 => Instruction::StoreBcd(x),
+                0x55 => Instruction::Store(x),
+                0x65 => Instruction::LoadRegs(x),
+                _ => return None,
+            },
+        };
+        Some(instruction)
+    }
+}
+
+// Classic Cowgod mnemonics
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Sys(nnn) => write!(f, "SYS {:#05X}", nnn),
+            Instruction::Cls => write!(f, "CLS"),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::Jump(nnn) => write!(f, "JP {:#05X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL {:#05X}", nnn),
+            Instruction::SkipEq(x, kk) => write!(f, "SE V{:X}, {:#04X}", x, kk),
+            Instruction::SkipNe(x, kk) => write!(f, "SNE V{:X}, {:#04X}", x, kk),
+            Instruction::SkipEqReg(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::Load(x, kk) => write!(f, "LD V{:X}, {:#04X}", x, kk),
+            Instruction::Add(x, kk) => write!(f, "ADD V{:X}, {:#04X}", x, kk),
+            Instruction::Move(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddReg(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubN(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipNeReg(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI(nnn) => write!(f, "LD I, {:#05X}", nnn),
+            Instruction::JumpV0(nnn) => write!(f, "JP V0, {:#05X}", nnn),
+            Instruction::Random(x, kk) => write!(f, "RND V{:X}, {:#04X}", x, kk),
+            Instruction::Draw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipKey(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipNoKey(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::LoadDelay(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitKey(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddI(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::LoadFont(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::StoreBcd(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::Store(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadRegs(x) => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
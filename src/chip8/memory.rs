@@ -1,33 +1,123 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use crate::chip8::memory::layout::{MemoryLayout, Region};
 use crate::exceptions::Exception;
-use crate::exceptions::ExceptionType::AddressOutOfRange;
+use crate::exceptions::ExceptionType::{AddressOutOfRange, BadArgument, ReservedWrite};
 
-const RAM_MAX: usize = 4096;
+pub mod layout;
+
+pub const RAM_MAX: usize = 4096;
+
+// Address written in hexadecimal, with or without 0x
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Address(pub u16);
+
+impl FromStr for Address {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<Address, Exception> {
+        u16::from_str_radix(s.trim_start_matches("0x"), 16)
+            .map(Address)
+            .map_err(|_| Exception::new(BadArgument))
+    }
+}
+
+// What happens when the program writes to a reserved region
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReservedWrites {
+    Allow,
+    // Warns once per address
+    Warn,
+    Trap,
+}
+
+impl FromStr for ReservedWrites {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<ReservedWrites, Exception> {
+        match s {
+            "allow" => Ok(ReservedWrites::Allow),
+            "warn" => Ok(ReservedWrites::Warn),
+            "trap" => Ok(ReservedWrites::Trap),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+// What happens on an access past the end of the RAM
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutOfRange {
+    Error,
+    // Only the low 12 bits of the address are used, as on the VIP
+    Wrap,
+}
+
+impl FromStr for OutOfRange {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<OutOfRange, Exception> {
+        match s {
+            "error" => Ok(OutOfRange::Error),
+            "wrap" => Ok(OutOfRange::Wrap),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
 
 pub struct RandomAccessMemory {
-    memory: [u8; RAM_MAX]
+    memory: [u8; RAM_MAX],
+    layout: MemoryLayout,
+    reserved_writes: ReservedWrites,
+    out_of_range: OutOfRange,
+    warned: BTreeSet<u16>,
 }
 
 impl RandomAccessMemory {
-    pub(crate) fn new() -> RandomAccessMemory {
+    pub(crate) fn new(layout: MemoryLayout, reserved_writes: ReservedWrites, out_of_range: OutOfRange) -> RandomAccessMemory {
         RandomAccessMemory {
-            memory: [0; RAM_MAX]
+            memory: [0; RAM_MAX],
+            layout,
+            reserved_writes,
+            out_of_range,
+            warned: BTreeSet::new(),
         }
     }
 
     pub(crate) fn read(&self, address: u16) -> Result<u8, Exception> {
-        if address < RAM_MAX as u16 {
-            Ok(self.memory[address as usize])
-        } else {
-            Err(Exception::new(AddressOutOfRange))
-        }
+        Ok(self.memory[self.index(address)?])
     }
 
     pub(crate) fn write(&mut self, address: u16, value: u8) -> Result<(), Exception> {
-        if address < RAM_MAX as u16 {
-            self.memory[address as usize] = value;
-            Ok(())
-        } else {
-            Err(Exception::new(AddressOutOfRange))
+        let index = self.index(address)?;
+        if let Some(region) = self.layout.region(index as u16).filter(Region::is_reserved) {
+            match self.reserved_writes {
+                ReservedWrites::Allow => {}
+                ReservedWrites::Warn => {
+                    if self.warned.insert(index as u16) {
+                        eprintln!("Warning: write to the {} at {:03X}", region, index);
+                    }
+                }
+                ReservedWrites::Trap => return Err(Exception::new(ReservedWrite)),
+            }
         }
+        self.memory[index] = value;
+        Ok(())
     }
-}
\ No newline at end of file
+
+    // Writes the font or the ROM, whatever the region
+    pub(crate) fn load(&mut self, address: u16, bytes: &[u8]) -> Result<(), Exception> {
+        let start = address as usize;
+        self.memory.get_mut(start..start + bytes.len())
+            .ok_or(Exception::new(AddressOutOfRange))?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn index(&self, address: u16) -> Result<usize, Exception> {
+        match self.out_of_range {
+            _ if (address as usize) < RAM_MAX => Ok(address as usize),
+            OutOfRange::Wrap => Ok(address as usize % RAM_MAX),
+            OutOfRange::Error => Err(Exception::new(AddressOutOfRange)),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::chip8::font::FONT_SIZE;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, RomTooLarge};
+
+pub const PROGRAM_START: u16 = 0x200;
+// The VIP keeps its stack and variables at EA0 and the display at F00
+const VIP_STACK: u16 = 0xEA0;
+const VIP_DISPLAY: u16 = 0xF00;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layout {
+    // The whole RAM after the interpreter is free
+    Modern,
+    Vip,
+}
+
+impl FromStr for Layout {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<Layout, Exception> {
+        match s {
+            "modern" => Ok(Layout::Modern),
+            "vip" => Ok(Layout::Vip),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Region {
+    Interpreter,
+    Font,
+    Program,
+    Free,
+    Stack,
+    Display,
+}
+
+impl Region {
+    // Regions the program isn't expected to write to
+    pub fn is_reserved(&self) -> bool {
+        !matches!(self, Region::Program | Region::Free)
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Region::Interpreter => "interpreter area",
+            Region::Font => "font",
+            Region::Program => "program",
+            Region::Free => "free RAM",
+            Region::Stack => "stack area",
+            Region::Display => "display buffer",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// What each address of the RAM holds, as consecutive regions
+pub struct MemoryLayout {
+    regions: Vec<(u16, u16, Region)>,
+}
+
+impl MemoryLayout {
+    // Fails when the ROM doesn't fit between the interpreter and the end of
+    // the free RAM, or the font doesn't fit in the interpreter area
+    pub fn new(layout: Layout, font_address: u16, rom_size: usize, ram_size: u16) -> Result<MemoryLayout, Exception> {
+        let font_end = font_address as usize + FONT_SIZE as usize;
+        if font_end > PROGRAM_START as usize {
+            return Err(Exception::new(BadArgument));
+        }
+        let end = match layout {
+            Layout::Modern => ram_size,
+            Layout::Vip => VIP_STACK,
+        };
+        let space = (end - PROGRAM_START) as usize;
+        if rom_size > space {
+            return Err(Exception::new(RomTooLarge { size: rom_size, space }));
+        }
+
+        let font_end = font_end as u16;
+        let program_end = PROGRAM_START + rom_size as u16;
+        let mut regions = vec![
+            (0, font_address, Region::Interpreter),
+            (font_address, font_end, Region::Font),
+            (font_end, PROGRAM_START, Region::Interpreter),
+            (PROGRAM_START, program_end, Region::Program),
+            (program_end, end, Region::Free),
+        ];
+        if layout == Layout::Vip {
+            regions.push((VIP_STACK, VIP_DISPLAY, Region::Stack));
+            regions.push((VIP_DISPLAY, ram_size, Region::Display));
+        }
+        regions.retain(|&(start, end, _)| start < end);
+        Ok(MemoryLayout { regions })
+    }
+
+    pub fn region(&self, address: u16) -> Option<Region> {
+        self.regions.iter()
+            .find(|&&(start, end, _)| (start..end).contains(&address))
+            .map(|&(_, _, region)| region)
+    }
+}
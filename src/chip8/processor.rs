@@ -1,8 +1,15 @@
 use rand::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
+use crate::chip8::coverage::Coverage;
+use crate::chip8::font::{DEFAULT_FONT_ADDRESS, Font};
+use crate::chip8::instruction::Instruction;
 use crate::chip8::memory::RandomAccessMemory;
-use crate::device::display::Display;
+use crate::chip8::profiler::Profiler;
+use crate::chip8::sprites::SpriteCollector;
+use crate::chip8::stack::{CallStack, DEFAULT_DEPTH};
+use crate::chip8::tracer::{CpuState, Tracer};
+use crate::device::framebuffer::Framebuffer;
 use crate::device::keyboard::Keyboard;
 use crate::device::sprite::Sprite;
 use crate::exceptions::{Exception, ExceptionType};
@@ -14,16 +21,21 @@ pub struct Processor {
     pub(crate) st: u8,
 
     program_counter: u16,
-    stack: [u16; 16],
-    stack_ptr: u16,
+    font_address: u16,
+    pub(crate) stack: CallStack,
 
     memory: Rc<RefCell<RandomAccessMemory>>,
-    display: Rc<RefCell<Display>>,
+    framebuffer: Rc<RefCell<Framebuffer>>,
     keyboard: Rc<RefCell<Keyboard>>,
+
+    pub(crate) profiler: Option<Profiler>,
+    pub(crate) coverage: Option<Coverage>,
+    pub(crate) tracer: Option<Tracer>,
+    pub(crate) sprites: Option<SpriteCollector>,
 }
 
 impl Processor {
-    pub fn new(ram: Rc<RefCell<RandomAccessMemory>>, display: Rc<RefCell<Display>>,
+    pub fn new(ram: Rc<RefCell<RandomAccessMemory>>, framebuffer: Rc<RefCell<Framebuffer>>,
                keyboard: Rc<RefCell<Keyboard>>) -> Processor {
        Processor {
            reg_v: [0; 16],
@@ -31,133 +43,124 @@ impl Processor {
            dt: 0,
            st: 0,
            program_counter: 512,
-           stack: [0; 16],
-           stack_ptr: 0,
+           font_address: DEFAULT_FONT_ADDRESS,
+           stack: CallStack::new(DEFAULT_DEPTH),
 
            memory: ram,
-           display,
+           framebuffer,
            keyboard,
+
+           profiler: None,
+           coverage: None,
+           tracer: None,
+           sprites: None,
        }
     }
 
     pub fn fetch_decode_execute(&mut self) -> Result<(), Exception> {
         let part1 = self.memory.borrow().read(self.program_counter)?;
-        let part2 = self.memory.borrow().read(self.program_counter + 1)?;
-        let mut instr: u16 = (part1 as u16) << 8;
-        instr = instr + part2 as u16;
-
-        // println!("Instr : {:X} - {}", instr, self.program_counter);
-
-        self.program_counter += 2;
-
-        if instr == 0x00E0 {
-            self.processor_00e0_cls()
-        } else if instr == 0x00EE {
-            self.processor_00ee_ret()
-        } else if (instr & 0xF000) == 0 {
-            self.processor_0nnn_sys(instr)
-        } else if (instr & 0xF000) == 0x1000 {
-            self.processor_1nnn_jpt(instr & 0x0FFF)
-        } else if (instr & 0xF000) == 0x2000 {
-            self.processor_2nnn_call(instr & 0x0FFF)
-        } else if (instr & 0xF000) == 0x3000 {
-            self.processor_3xkk_se(((instr & 0x0F00) >> 8) as u8, (instr & 0x00FF) as u8)
-        } else if (instr & 0xF000) == 0x4000 {
-            self.processor_4xkk_sne(((instr & 0x0F00) >> 8) as u8, (instr & 0x00FF) as u8)
-        } else if (instr & 0xF000) == 0x5000 {
-            self.processor_5xy0_sereg(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF000) == 0x6000 {
-            self.processor_6xkk_ldval(((instr & 0x0F00) >> 8) as u8, (instr & 0x00FF) as u8)
-        } else if (instr & 0xF000) == 0x7000 {
-            self.processor_7xkk_add(((instr & 0x0F00) >> 8) as u8, (instr & 0x00FF) as u8)
-        } else if (instr & 0xF00F) == 0x8000 {
-            self.processor_8xy0_ldreg(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8001 {
-            self.processor_8xy1_or(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8002 {
-            self.processor_8xy2_and(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8003 {
-            self.processor_8xy3_xor(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8004 {
-            self.processor_8xy4_addc(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8005 {
-            self.processor_8xy5_sub(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8006 {
-            self.processor_8xy6_shr(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x8007 {
-            self.processor_8xy7_subn(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF00F) == 0x800E {
-            self.processor_8xye_shl(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF000) == 0x9000 {
-            self.processor_9xy0_sne_reg(((instr & 0x0F00) >> 8) as u8, ((instr & 0x00F0) >> 4) as u8)
-        } else if (instr & 0xF000) == 0xA000 {
-            self.processor_annn_ldi(instr & 0x0FFF)
-        } else if (instr & 0xF000) == 0xB000 {
-            self.processor_bnnn_jpv0(instr & 0x0FFF)
-        } else if (instr & 0xF000) == 0xC000 {
-            self.processor_cxkk_rnd(((instr & 0x0F00) >> 8) as u8, (instr & 0x00FF) as u8)
-        } else if (instr & 0xF000) == 0xD000 {
-            self.processor_dxyn_drw(((instr & 0x0F00) >> 8) as u8,
-                                    ((instr & 0x00F0) >> 4) as u8,
-                                    (instr & 0x000F) as u8)
-        } else if (instr & 0xF0FF) == 0xE09E {
-            self.processor_ex9e_skp(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xE0A1 {
-            self.processor_exa1_sknp(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF007 {
-            self.processor_fx07_lddt(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF00A {
-            self.processor_fx0a_ldvk(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF015 {
-            self.processor_fx15_lddt(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF018 {
-            self.processor_fx18_ldst(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF01E {
-            self.processor_fx1e_addi(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF029 {
-            self.processor_fx29_ldf(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF033 {
-            self.processor_fx33_ldb(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF055 {
-            self.processor_fx55_ldw(((instr & 0x0F00) >> 8) as u8)
-        } else if (instr & 0xF0FF) == 0xF065 {
-            self.processor_fx65_ldr(((instr & 0x0F00) >> 8) as u8)
-        } else {
-            Err(Exception::new(ExceptionType::BadInstruction))
-        }
-    }
-
-    pub(crate) fn load_sprites(&mut self) -> Result<(), Exception> {
-        let sprite_list: [[u8; 5]; 16] = [
-            [0xF0, 0x90, 0x90, 0x90, 0xF0], // 0
-            [0x20, 0x60, 0x20, 0x20, 0x70], // 1
-            [0xF0, 0x10, 0xF0, 0x80, 0xF0], // 2
-            [0xF0, 0x10, 0xF0, 0x10, 0xF0], // 3
-            [0x90, 0x90, 0xF0, 0x10, 0x10], // 4
-            [0xF0, 0x80, 0xF0, 0x10, 0xF0], // 5
-            [0xF0, 0x80, 0xF0, 0x90, 0xF0], // 6
-            [0xF0, 0x10, 0x20, 0x40, 0x40], // 7
-            [0xF0, 0x90, 0xF0, 0x90, 0xF0], // 8
-            [0xF0, 0x90, 0xF0, 0x10, 0xF0], // 9
-            [0xF0, 0x90, 0xF0, 0x90, 0x90], // A
-            [0xE0, 0x90, 0xE0, 0x90, 0xE0], // B
-            [0xF0, 0x80, 0x80, 0x80, 0xF0], // C
-            [0xE0, 0x90, 0x90, 0x90, 0xE0], // D
-            [0xF0, 0x80, 0xF0, 0x80, 0xF0], // E
-            [0xF0, 0x80, 0xF0, 0x80, 0x80]  // F
-        ];
-
-        for (i, sprite) in sprite_list.iter().enumerate() {
-            for (j, &byte) in sprite.iter().enumerate() {
-                self.memory.borrow_mut().write((431 + i * 5 + j) as u16, byte)?;
-            }
+        let part2 = self.memory.borrow().read(self.program_counter.wrapping_add(1))?;
+        let instr: u16 = ((part1 as u16) << 8) | part2 as u16;
+
+        let address = self.program_counter;
+        let instruction = Instruction::decode(instr)
+            .ok_or(Exception::new(ExceptionType::BadInstruction).at(address))?;
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(address, instruction);
         }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(address, instruction, self.i);
+        }
+
+        let traced = self.tracer.as_ref().is_some_and(|tracer| tracer.wants(address, instr));
+        let before = self.state();
 
+        self.program_counter = self.program_counter.wrapping_add(2);
+        let result = self.execute(instruction).map_err(|e| e.at(address));
+
+        // Logged even when failing, to see what the instruction was
+        if traced {
+            let after = self.state();
+            self.tracer.as_mut().unwrap().log(address, instr, instruction, &before, &after)?;
+        }
+        result?;
+
+        if let Some(coverage) = &mut self.coverage {
+            if matches!(instruction, Instruction::SkipEq(..) | Instruction::SkipNe(..) | Instruction::SkipEqReg(..)
+                | Instruction::SkipNeReg(..) | Instruction::SkipKey(_) | Instruction::SkipNoKey(_)) {
+                coverage.record_branch(address, self.program_counter == address.wrapping_add(4));
+            }
+        }
         Ok(())
     }
 
+    pub(crate) fn state(&self) -> CpuState {
+        CpuState {
+            pc: self.program_counter,
+            i: self.i,
+            sp: self.stack.frames().len() as u8,
+            dt: self.dt,
+            st: self.st,
+            v: self.reg_v,
+        }
+    }
+
+    // The stack isn't changed, SP being only its depth
+    pub(crate) fn set_state(&mut self, state: &CpuState) {
+        self.program_counter = state.pc;
+        self.i = state.i;
+        self.dt = state.dt;
+        self.st = state.st;
+        self.reg_v = state.v;
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<(), Exception> {
+        match instruction {
+            Instruction::Sys(address) => self.processor_0nnn_sys(address),
+            Instruction::Cls => self.processor_00e0_cls(),
+            Instruction::Ret => self.processor_00ee_ret(),
+            Instruction::Jump(address) => self.processor_1nnn_jpt(address),
+            Instruction::Call(address) => self.processor_2nnn_call(address),
+            Instruction::SkipEq(reg, val) => self.processor_3xkk_se(reg, val),
+            Instruction::SkipNe(reg, val) => self.processor_4xkk_sne(reg, val),
+            Instruction::SkipEqReg(reg1, reg2) => self.processor_5xy0_sereg(reg1, reg2),
+            Instruction::Load(reg, val) => self.processor_6xkk_ldval(reg, val),
+            Instruction::Add(reg, val) => self.processor_7xkk_add(reg, val),
+            Instruction::Move(reg1, reg2) => self.processor_8xy0_ldreg(reg1, reg2),
+            Instruction::Or(reg1, reg2) => self.processor_8xy1_or(reg1, reg2),
+            Instruction::And(reg1, reg2) => self.processor_8xy2_and(reg1, reg2),
+            Instruction::Xor(reg1, reg2) => self.processor_8xy3_xor(reg1, reg2),
+            Instruction::AddReg(reg1, reg2) => self.processor_8xy4_addc(reg1, reg2),
+            Instruction::Sub(reg1, reg2) => self.processor_8xy5_sub(reg1, reg2),
+            Instruction::ShiftRight(reg1, reg2) => self.processor_8xy6_shr(reg1, reg2),
+            Instruction::SubN(reg1, reg2) => self.processor_8xy7_subn(reg1, reg2),
+            Instruction::ShiftLeft(reg1, reg2) => self.processor_8xye_shl(reg1, reg2),
+            Instruction::SkipNeReg(reg1, reg2) => self.processor_9xy0_sne_reg(reg1, reg2),
+            Instruction::LoadI(address) => self.processor_annn_ldi(address),
+            Instruction::JumpV0(address) => self.processor_bnnn_jpv0(address),
+            Instruction::Random(reg, val) => self.processor_cxkk_rnd(reg, val),
+            Instruction::Draw(reg1, reg2, nibble) => self.processor_dxyn_drw(reg1, reg2, nibble),
+            Instruction::SkipKey(reg) => self.processor_ex9e_skp(reg),
+            Instruction::SkipNoKey(reg) => self.processor_exa1_sknp(reg),
+            Instruction::LoadDelay(reg) => self.processor_fx07_lddt(reg),
+            Instruction::WaitKey(reg) => self.processor_fx0a_ldvk(reg),
+            Instruction::SetDelay(reg) => self.processor_fx15_lddt(reg),
+            Instruction::SetSound(reg) => self.processor_fx18_ldst(reg),
+            Instruction::AddI(reg) => self.processor_fx1e_addi(reg),
+            Instruction::LoadFont(reg) => self.processor_fx29_ldf(reg),
+            Instruction::StoreBcd(reg) => self.processor_fx33_ldb(reg),
+            Instruction::Store(reg) => self.processor_fx55_ldw(reg),
+            Instruction::LoadRegs(reg) => self.processor_fx65_ldr(reg),
+        }
+    }
+
+    pub(crate) fn load_font(&mut self, font: &Font) -> Result<(), Exception> {
+        self.font_address = font.address;
+        self.memory.borrow_mut().load(font.address, &font.glyphs)
+    }
+
     fn processor_0nnn_sys(&mut self, address: u16) -> Result<(), Exception> {
-        if address <= 4095 && address >= 512 {
+        if (512..=4095).contains(&address) {
             self.program_counter = address;
             Ok(())
         } else {
@@ -166,20 +169,13 @@ impl Processor {
     }
 
     fn processor_00e0_cls(&mut self) -> Result<(), Exception> {
-        match self.display.borrow_mut().clear() {
-            Ok(_) => Ok(()),
-            Err(_) => Err(Exception::new(ExceptionType::SDL))
-        }
+        self.framebuffer.borrow_mut().clear();
+        Ok(())
     }
 
     fn processor_00ee_ret(&mut self) -> Result<(), Exception> {
-        if self.stack_ptr == 0 {
-            Err(Exception::new(ExceptionType::StackPointerOutOfRange))
-        } else {
-            self.program_counter = self.stack[self.stack_ptr as usize];
-            self.stack_ptr -= 1;
-            Ok(())
-        }
+        self.program_counter = self.stack.pop()?;
+        Ok(())
     }
 
     fn processor_1nnn_jpt(&mut self, address: u16) -> Result<(), Exception> {
@@ -192,14 +188,9 @@ impl Processor {
     }
 
     fn processor_2nnn_call(&mut self, address: u16) -> Result<(), Exception> {
-        if self.stack_ptr > 15 {
-            Err(Exception::new(ExceptionType::StackPointerOutOfRange))
-        } else {
-            self.stack_ptr += 1;
-            self.stack[self.stack_ptr as usize] = self.program_counter;
-            self.program_counter = address;
-            Ok(())
-        }
+        self.stack.push(address, self.program_counter)?;
+        self.program_counter = address;
+        Ok(())
     }
 
     fn processor_3xkk_se(&mut self, reg: u8, val: u8) -> Result<(), Exception> {
@@ -207,7 +198,7 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         if self.reg_v[reg as usize] == val {
-            self.program_counter += 2;
+            self.program_counter = self.program_counter.wrapping_add(2);
             Ok(())
         } else {
             Ok(())
@@ -219,7 +210,7 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         if self.reg_v[reg as usize] != val {
-            self.program_counter += 2;
+            self.program_counter = self.program_counter.wrapping_add(2);
             Ok(())
         } else {
             Ok(())
@@ -231,7 +222,7 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         if self.reg_v[reg1 as usize] == self.reg_v[reg2 as usize] {
-            self.program_counter += 2;
+            self.program_counter = self.program_counter.wrapping_add(2);
             Ok(())
         } else {
             Ok(())
@@ -267,7 +258,7 @@ impl Processor {
         if reg1 > 15 || reg2 > 15 {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
-        self.reg_v[reg1 as usize] = self.reg_v[reg1 as usize] | self.reg_v[reg2 as usize];
+        self.reg_v[reg1 as usize] |= self.reg_v[reg2 as usize];
         self.reg_v[15] = 0;
         Ok(())
     }
@@ -276,7 +267,7 @@ impl Processor {
         if reg1 > 15 || reg2 > 15 {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
-        self.reg_v[reg1 as usize] = self.reg_v[reg1 as usize] & self.reg_v[reg2 as usize];
+        self.reg_v[reg1 as usize] &= self.reg_v[reg2 as usize];
         self.reg_v[15] = 0;
         Ok(())
     }
@@ -285,7 +276,7 @@ impl Processor {
         if reg1 > 15 || reg2 > 15 {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
-        self.reg_v[reg1 as usize] = self.reg_v[reg1 as usize] ^ self.reg_v[reg2 as usize];
+        self.reg_v[reg1 as usize] ^= self.reg_v[reg2 as usize];
         self.reg_v[15] = 0;
         Ok(())
     }
@@ -368,7 +359,7 @@ impl Processor {
 
     fn processor_9xy0_sne_reg(&mut self, reg1: u8, reg2: u8) -> Result<(), Exception> {
         if self.reg_v[reg1 as usize] != self.reg_v[reg2 as usize] {
-            self.program_counter += 2;
+            self.program_counter = self.program_counter.wrapping_add(2);
             Ok(())
         } else {
             Ok(())
@@ -410,18 +401,18 @@ impl Processor {
         }
         let mut sprite_content: Vec<u8> = Vec::with_capacity(nibble as usize);
 
-        for i in self.i..self.i + nibble as u16 {
-            if i >= 4096 {
-                return Err(Exception::new(ExceptionType::SDL))
-            } else {
-                let byte = self.memory.borrow_mut().read(i)?;
-                sprite_content.push(byte);
-            }
+        for i in 0..nibble {
+            let byte = self.memory.borrow_mut().read(self.i_offset(i)?)?;
+            sprite_content.push(byte);
         }
 
         let sprite = Sprite::new_with_content(sprite_content);
+        if let Some(sprites) = &mut self.sprites {
+            sprites.record(self.i, &sprite);
+        }
 
-        self.display.borrow_mut().draw(&sprite, self.reg_v[reg1 as usize], self.reg_v[reg2 as usize], &mut self.reg_v[15])?;
+        let collision = self.framebuffer.borrow_mut().draw(&sprite, self.reg_v[reg1 as usize], self.reg_v[reg2 as usize])?;
+        self.reg_v[15] = collision as u8;
         Ok(())
     }
 
@@ -430,7 +421,7 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         if self.keyboard.borrow_mut().get(self.reg_v[reg as usize]) == Some(1) {
-            self.program_counter += 2;
+            self.program_counter = self.program_counter.wrapping_add(2);
             Ok(())
         } else {
             Ok(())
@@ -442,7 +433,7 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         if self.keyboard.borrow_mut().get(self.reg_v[reg as usize]) == Some(0) {
-            self.program_counter += 2;
+            self.program_counter = self.program_counter.wrapping_add(2);
             Ok(())
         } else {
             Ok(())
@@ -485,7 +476,7 @@ impl Processor {
         if reg > 15 {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
-        self.i += self.reg_v[reg as usize] as u16;
+        self.i = self.i.wrapping_add(self.reg_v[reg as usize] as u16);
         Ok(())
     }
 
@@ -493,7 +484,8 @@ impl Processor {
         if reg > 15 {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
-        self.i = 431 + reg as u16 * 5;
+        // Only the low digit of VX has a glyph
+        self.i = self.font_address + (self.reg_v[reg as usize] & 0xF) as u16 * 5;
         Ok(())
     }
 
@@ -504,8 +496,8 @@ impl Processor {
         let value: u8 = self.reg_v[reg as usize];
 
         self.memory.borrow_mut().write(self.i, value / 100)?;
-        self.memory.borrow_mut().write(self.i + 1, (value / 10) % 10)?;
-        self.memory.borrow_mut().write(self.i + 2, value % 10)?;
+        self.memory.borrow_mut().write(self.i_offset(1)?, (value / 10) % 10)?;
+        self.memory.borrow_mut().write(self.i_offset(2)?, value % 10)?;
         Ok(())
     }
 
@@ -514,7 +506,7 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         for i in 0..=reg {
-            self.memory.borrow_mut().write(self.i + i as u16, self.reg_v[i as usize])?;
+            self.memory.borrow_mut().write(self.i_offset(i)?, self.reg_v[i as usize])?;
         }
         Ok(())
     }
@@ -524,8 +516,40 @@ impl Processor {
             return Err(Exception::new(ExceptionType::BadArgument))
         }
         for i in 0..=reg {
-            self.reg_v[i as usize] = self.memory.borrow_mut().read(self.i + i as u16)?;
+            self.reg_v[i as usize] = self.memory.borrow_mut().read(self.i_offset(i)?)?;
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    // Address `offset` bytes after I, which may be past the addressable range
+    fn i_offset(&self, offset: u8) -> Result<u16, Exception> {
+        self.i.checked_add(offset as u16).ok_or(Exception::new(ExceptionType::AddressOutOfRange))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::chip8::Chip8;
+    use crate::chip8::font::{DEFAULT_FONT_ADDRESS, Font, FontSet};
+    use crate::chip8::memory::{OutOfRange, ReservedWrites};
+    use crate::chip8::memory::layout::Layout;
+
+    // 200: LD V0, 0x1A; 202: LD F, V0
+    const ROM: [u8; 4] = [0x60, 0x1A, 0xF0, 0x29];
+
+    #[test]
+    fn fx29_uses_the_low_digit() {
+        let mut c8 = Chip8::from_rom(&ROM).unwrap();
+        c8.step().unwrap();
+        c8.step().unwrap();
+        assert_eq!(c8.state().i, DEFAULT_FONT_ADDRESS + 0xA * 5);
+    }
+
+    #[test]
+    fn fx29_follows_the_font_address() {
+        let font = Font::new(FontSet::Vip, 0x050);
+        let mut c8 = Chip8::new(&ROM, &font, Layout::Modern, ReservedWrites::Allow, OutOfRange::Error).unwrap();
+        c8.step().unwrap();
+        c8.step().unwrap();
+        assert_eq!(c8.state().i, 0x050 + 0xA * 5);
+    }
+}
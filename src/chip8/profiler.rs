@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use crate::chip8::instruction::Instruction;
+
+// Subroutines nested deeper are attributed to the deepest one tracked
+const MAX_DEPTH: usize = 64;
+// Lines of each section of the report
+const HOTSPOTS: usize = 20;
+
+// Rough COSMAC VIP instruction times in machine cycles, enough to compare the
+// cost of the parts of a program
+fn cycles(instruction: Instruction) -> u64 {
+    match instruction {
+        Instruction::Cls => 24,
+        Instruction::Ret => 10,
+        Instruction::Sys(_) | Instruction::Jump(_) | Instruction::LoadI(_) => 12,
+        Instruction::Call(_) => 26,
+        Instruction::SkipEq(..) | Instruction::SkipNe(..) | Instruction::SkipKey(_)
+        | Instruction::SkipNoKey(_) => 14,
+        Instruction::SkipEqReg(..) | Instruction::SkipNeReg(..) => 18,
+        Instruction::Load(..) => 6,
+        Instruction::Add(..) => 10,
+        Instruction::Move(..) | Instruction::Or(..) | Instruction::And(..) | Instruction::Xor(..)
+        | Instruction::AddReg(..) | Instruction::Sub(..) | Instruction::ShiftRight(..)
+        | Instruction::SubN(..) | Instruction::ShiftLeft(..) => 44,
+        Instruction::JumpV0(_) => 22,
+        Instruction::Random(..) => 36,
+        Instruction::Draw(_, _, n) => 68 + 46 * n as u64,
+        Instruction::LoadDelay(_) | Instruction::SetDelay(_) | Instruction::SetSound(_) => 10,
+        Instruction::WaitKey(_) => 20,
+        Instruction::AddI(_) => 16,
+        Instruction::LoadFont(_) => 20,
+        Instruction::StoreBcd(_) => 84,
+        Instruction::Store(x) | Instruction::LoadRegs(x) => 14 + 14 * x as u64,
+    }
+}
+
+#[derive(Default)]
+struct Counter {
+    executions: u64,
+    cycles: u64,
+}
+
+struct Subroutine {
+    calls: u64,
+    // Spent in the subroutine itself, and with the ones it calls
+    own: Counter,
+    total: Counter,
+}
+
+// Counts what is executed at each address and in each subroutine, followed
+// through 2nnn and 00EE. The program's entry point counts as a subroutine.
+pub struct Profiler {
+    addresses: BTreeMap<u16, (Instruction, Counter)>,
+    subroutines: BTreeMap<u16, Subroutine>,
+    stack: Vec<u16>,
+    total: Counter,
+}
+
+impl Profiler {
+    pub fn new(entry: u16) -> Profiler {
+        let mut subroutines = BTreeMap::new();
+        subroutines.insert(entry, Subroutine { calls: 1, own: Counter::default(), total: Counter::default() });
+        Profiler {
+            addresses: BTreeMap::new(),
+            subroutines,
+            stack: vec![entry],
+            total: Counter::default(),
+        }
+    }
+
+    pub fn record(&mut self, address: u16, instruction: Instruction) {
+        let cost = cycles(instruction);
+        let add = |counter: &mut Counter| {
+            counter.executions += 1;
+            counter.cycles += cost;
+        };
+
+        add(&mut self.total);
+        add(&mut self.addresses.entry(address).or_insert((instruction, Counter::default())).1);
+        let current = *self.stack.last().unwrap();
+        add(&mut self.subroutines.get_mut(&current).unwrap().own);
+        // Recursive subroutines are only counted once in the totals
+        let mut seen = Vec::with_capacity(self.stack.len());
+        for &subroutine in &self.stack {
+            if !seen.contains(&subroutine) {
+                seen.push(subroutine);
+                add(&mut self.subroutines.get_mut(&subroutine).unwrap().total);
+            }
+        }
+
+        match instruction {
+            Instruction::Call(target) if self.stack.len() < MAX_DEPTH => {
+                self.stack.push(target);
+                self.subroutines.entry(target)
+                    .or_insert(Subroutine { calls: 0, own: Counter::default(), total: Counter::default() })
+                    .calls += 1;
+            }
+            // A return from the entry point is left to the processor to report
+            Instruction::Ret if self.stack.len() > 1 => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn report(&self, frames: u64) -> String {
+        let mut report = String::new();
+        let percent = |cycles: u64| 100.0 * cycles as f64 / self.total.cycles.max(1) as f64;
+
+        writeln!(report, "Profile: {} instructions, {} cycles over {} frames ({:.0} cycles per frame)",
+                 self.total.executions, self.total.cycles, frames,
+                 self.total.cycles as f64 / frames.max(1) as f64).unwrap();
+
+        writeln!(report, "\nHotspots:").unwrap();
+        let mut addresses: Vec<_> = self.addresses.iter().collect();
+        addresses.sort_by(|a, b| b.1.1.cycles.cmp(&a.1.1.cycles).then(a.0.cmp(b.0)));
+        for (address, (instruction, counter)) in addresses.iter().take(HOTSPOTS) {
+            writeln!(report, "  {:#05X}  {:>6.2}%  {:>10} x  {}",
+                     address, percent(counter.cycles), counter.executions, instruction).unwrap();
+        }
+
+        writeln!(report, "\nSubroutines:            self    total      calls").unwrap();
+        let mut subroutines: Vec<_> = self.subroutines.iter().collect();
+        subroutines.sort_by(|a, b| b.1.total.cycles.cmp(&a.1.total.cycles).then(a.0.cmp(b.0)));
+        for (address, subroutine) in subroutines.iter().take(HOTSPOTS) {
+            writeln!(report, "  {:#05X}             {:>6.2}%  {:>6.2}%  {:>9}",
+                     address, percent(subroutine.own.cycles), percent(subroutine.total.cycles),
+                     subroutine.calls).unwrap();
+        }
+
+        writeln!(report, "\nAnnotated disassembly:").unwrap();
+        let mut previous = None;
+        for (&address, (instruction, counter)) in &self.addresses {
+            if self.subroutines.contains_key(&address) {
+                writeln!(report, "sub_{:03X}:", address).unwrap();
+            } else if previous.is_some_and(|previous| previous + 2 != address) {
+                writeln!(report, "  ...").unwrap();
+            }
+            writeln!(report, "  {:#05X}  {:>6.2}%  {:>10} x  {}",
+                     address, percent(counter.cycles), counter.executions, instruction).unwrap();
+            previous = Some(address);
+        }
+        report
+    }
+}
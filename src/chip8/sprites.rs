@@ -0,0 +1,39 @@
+use std::collections::BTreeSet;
+use crate::device::sprite::Sprite;
+
+pub struct DrawnSprite {
+    // Value of I when it was drawn
+    pub address: u16,
+    pub first_frame: u64,
+    pub rows: Vec<u8>,
+}
+
+// Every distinct sprite drawn by Dxyn, in the order they are first drawn
+pub struct SpriteCollector {
+    pub sprites: Vec<DrawnSprite>,
+    seen: BTreeSet<(u16, Vec<u8>)>,
+    pub frame: u64,
+}
+
+impl Default for SpriteCollector {
+    fn default() -> SpriteCollector {
+        SpriteCollector::new()
+    }
+}
+
+impl SpriteCollector {
+    pub fn new() -> SpriteCollector {
+        SpriteCollector {
+            sprites: Vec::new(),
+            seen: BTreeSet::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn record(&mut self, address: u16, sprite: &Sprite) {
+        let rows: Vec<u8> = (0..sprite.length()).filter_map(|row| sprite.get(row).ok()).collect();
+        if !rows.is_empty() && self.seen.insert((address, rows.clone())) {
+            self.sprites.push(DrawnSprite { address, first_frame: self.frame, rows });
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{StackOverflow, StackUnderflow};
+
+// The VIP interpreter had room for 12 return addresses, SCHIP for 16
+pub const DEFAULT_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StackFrame {
+    // Address of the subroutine, as given to 2nnn
+    pub subroutine: u16,
+    pub return_address: u16,
+}
+
+// Subroutines being run, the innermost last
+pub struct CallStack {
+    frames: Vec<StackFrame>,
+    depth: usize,
+}
+
+impl CallStack {
+    pub fn new(depth: usize) -> CallStack {
+        CallStack {
+            frames: Vec::with_capacity(depth),
+            depth,
+        }
+    }
+
+    pub fn push(&mut self, subroutine: u16, return_address: u16) -> Result<(), Exception> {
+        if self.frames.len() >= self.depth {
+            return Err(Exception::new(StackOverflow));
+        }
+        self.frames.push(StackFrame { subroutine, return_address });
+        Ok(())
+    }
+
+    // Return address of the innermost subroutine
+    pub fn pop(&mut self) -> Result<u16, Exception> {
+        self.frames.pop()
+            .map(|frame| frame.return_address)
+            .ok_or(Exception::new(StackUnderflow))
+    }
+
+    pub fn frames(&self) -> &[StackFrame] {
+        &self.frames
+    }
+}
+
+// Call chain, innermost first, with where each subroutine was called from
+impl fmt::Display for CallStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Call chain ({}/{}):", self.frames.len(), self.depth)?;
+        for frame in self.frames.iter().rev() {
+            writeln!(f, "  {:03X}  called from {:03X}, returns to {:03X}",
+                     frame.subroutine, frame.return_address.wrapping_sub(2), frame.return_address)?;
+        }
+        writeln!(f, "  main")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::Chip8;
+
+    #[test]
+    fn push_up_to_depth() {
+        let mut stack = CallStack::new(3);
+        for call in 0..3 {
+            assert!(stack.push(0x300, 0x202 + call * 2).is_ok());
+        }
+        assert_eq!(stack.frames().len(), 3);
+        assert_eq!(stack.push(0x300, 0x208).unwrap_err().to_string(), "Exception: StackOverflow");
+        assert_eq!(stack.frames().len(), 3);
+        assert_eq!(stack.pop().unwrap(), 0x206);
+    }
+
+    #[test]
+    fn overflow_at_depth_plus_one() {
+        // 200: CALL 200
+        let mut c8 = Chip8::from_rom(&[0x22, 0x00]).unwrap();
+        c8.set_stack_depth(4);
+        for _ in 0..4 {
+            c8.step().unwrap();
+        }
+        assert_eq!(c8.call_stack().frames().len(), 4);
+        assert_eq!(c8.step().unwrap_err().to_string(), "Exception: StackOverflow at 200");
+    }
+
+    #[test]
+    fn underflow_on_empty_stack() {
+        // 200: CLS, 202: RET
+        let mut c8 = Chip8::from_rom(&[0x00, 0xE0, 0x00, 0xEE]).unwrap();
+        c8.step().unwrap();
+        assert_eq!(c8.step().unwrap_err().to_string(), "Exception: StackUnderflow at 202");
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use crate::chip8::instruction::Instruction;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, File as FileError};
+
+// Inclusive range written `start-end`, either end being optional. Addresses
+// are in hexadecimal and frames in decimal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Window<T> {
+    start: Option<T>,
+    end: Option<T>,
+}
+
+impl<T: PartialOrd + Copy> Window<T> {
+    pub fn contains(&self, value: T) -> bool {
+        self.start.is_none_or(|start| value >= start) && self.end.is_none_or(|end| value <= end)
+    }
+
+    fn parse(s: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Window<T>, Exception> {
+        let bound = |s: &str| match s.trim() {
+            "" => Ok(None),
+            s => parse(s).map(Some).ok_or(Exception::new(BadArgument)),
+        };
+        match s.split_once('-') {
+            Some((start, end)) => Ok(Window { start: bound(start)?, end: bound(end)? }),
+            None => {
+                let value = bound(s)?;
+                Ok(Window { start: value, end: value })
+            }
+        }
+    }
+}
+
+pub type AddressRange = Window<u16>;
+pub type FrameWindow = Window<u64>;
+
+impl FromStr for AddressRange {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<AddressRange, Exception> {
+        Window::parse(s, |s| u16::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+    }
+}
+
+impl FromStr for FrameWindow {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<FrameWindow, Exception> {
+        Window::parse(s, |s| s.parse().ok())
+    }
+}
+
+// Opcode classes by their first hexadecimal digit, written like `1,2,D`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OpcodeClasses(u16);
+
+impl OpcodeClasses {
+    fn contains(&self, opcode: u16) -> bool {
+        self.0 & (1 << (opcode >> 12)) != 0
+    }
+}
+
+impl FromStr for OpcodeClasses {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<OpcodeClasses, Exception> {
+        let mut classes = 0;
+        for class in s.split(',').map(str::trim) {
+            let digit = u16::from_str_radix(class, 16).ok()
+                .filter(|_| class.len() == 1)
+                .ok_or(Exception::new(BadArgument))?;
+            classes |= 1 << digit;
+        }
+        Ok(OpcodeClasses(classes))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceFormat {
+    // The instruction and the registers it changed
+    Delta,
+    // The whole CPU state after the instruction, as read by `diff`
+    State,
+}
+
+impl FromStr for TraceFormat {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<TraceFormat, Exception> {
+        match s {
+            "delta" => Ok(TraceFormat::Delta),
+            "state" => Ok(TraceFormat::State),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct TraceFilter {
+    pub addresses: Option<AddressRange>,
+    pub classes: Option<OpcodeClasses>,
+    pub frames: Option<FrameWindow>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CpuState {
+    pub pc: u16,
+    pub i: u16,
+    pub sp: u8,
+    pub dt: u8,
+    pub st: u8,
+    pub v: [u8; 16],
+}
+
+impl fmt::Display for CpuState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PC={:03X} I={:03X} SP={:X} DT={:02X} ST={:02X} V=", self.pc, self.i, self.sp, self.dt, self.st)?;
+        for v in &self.v {
+            write!(f, "{:02X}", v)?;
+        }
+        Ok(())
+    }
+}
+
+// Logs the executed instructions, one line each. The delta format is
+//   <frame> <pc> <opcode> <mnemonic> I=<i> [Vx=<old>-><new>]... [I=<old>-><new>]
+// and the state one
+//   PC=<pc> I=<i> SP=<sp> DT=<dt> ST=<st> V=<V0 to VF>
+// Every field has a fixed format so that two traces can be diffed.
+pub struct Tracer {
+    writer: Box<dyn Write>,
+    filter: TraceFilter,
+    format: TraceFormat,
+    pub frame: u64,
+}
+
+impl Tracer {
+    // `-` traces to the standard output
+    pub fn new(path: &str, filter: TraceFilter, format: TraceFormat) -> Result<Tracer, Exception> {
+        let writer: Box<dyn Write> = match path {
+            "-" => Box::new(io::stdout()),
+            _ => Box::new(BufWriter::new(File::create(path).map_err(|_| Exception::new(FileError))?)),
+        };
+        Ok(Tracer { writer, filter, format, frame: 0 })
+    }
+
+    pub fn wants(&self, address: u16, opcode: u16) -> bool {
+        self.filter.addresses.is_none_or(|range| range.contains(address))
+            && self.filter.classes.is_none_or(|classes| classes.contains(opcode))
+            && self.filter.frames.is_none_or(|frames| frames.contains(self.frame))
+    }
+
+    pub fn log(&mut self, address: u16, opcode: u16, instruction: Instruction,
+               before: &CpuState, after: &CpuState) -> Result<(), Exception> {
+        if self.format == TraceFormat::State {
+            return writeln!(self.writer, "{}", after).map_err(|_| Exception::new(FileError));
+        }
+
+        let mut line = format!("{:06} {:03X} {:04X} {:<16} I={:03X}",
+                               self.frame, address, opcode, instruction.to_string(), before.i);
+        for (register, (old, new)) in before.v.iter().zip(after.v.iter()).enumerate() {
+            if old != new {
+                line.push_str(&format!(" V{:X}={:02X}->{:02X}", register, old, new));
+            }
+        }
+        if before.i != after.i {
+            line.push_str(&format!(" I={:03X}->{:03X}", before.i, after.i));
+        }
+        writeln!(self.writer, "{}", line).map_err(|_| Exception::new(FileError))
+    }
+}
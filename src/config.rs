@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::BadArgument;
+
+pub const DEFAULT_PATH: &str = "chip8.cfg";
+
+// Settings file made of `key = value` lines. Keys before any section apply
+// to every ROM, keys under a `[rom.ch8]` section only to the ROM with that
+// file name.
+pub struct Config {
+    sections: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config { sections: HashMap::new() }
+    }
+
+    pub fn load(path: &str) -> Result<Config, Exception> {
+        let content = fs::read_to_string(path).map_err(|_| Exception::new(BadArgument))?;
+        let mut config = Config::new();
+        let mut section = String::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+            } else if let Some((key, value)) = line.split_once('=') {
+                config.sections.entry(section.clone()).or_default()
+                    .push((key.trim().to_string(), value.trim().to_string()));
+            } else {
+                return Err(Exception::new(BadArgument));
+            }
+        }
+
+        Ok(config)
+    }
+
+    // Returns the settings of the ROM section followed by the global ones
+    pub fn settings(&self, rom: &str) -> Vec<(String, String)> {
+        let name = Path::new(rom).file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut settings = Vec::new();
+        for section in [name.as_str(), ""] {
+            if let Some(values) = self.sections.get(section) {
+                settings.extend(values.iter().cloned());
+            }
+        }
+        settings
+    }
+}
@@ -1,4 +1,7 @@
+pub(crate) use chip_eight::device::{framebuffer, keyboard, sprite};
+
 pub(crate) mod speaker;
 pub(crate) mod display;
-pub(crate) mod keyboard;
-pub(crate) mod sprite;
\ No newline at end of file
+pub(crate) mod palette;
+pub(crate) mod filter;
+pub(crate) mod crt;
\ No newline at end of file
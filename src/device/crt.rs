@@ -0,0 +1,96 @@
+use std::f32::consts::PI;
+use crate::device::filter::Image;
+use crate::device::palette::Rgb;
+
+// Width of the CRT output, the picture being upscaled so that scanlines and
+// curvature span several texture pixels
+const OUTPUT_WIDTH: usize = 512;
+
+#[derive(Clone, Copy)]
+pub struct CrtSettings {
+    pub scanlines: f32,
+    pub bloom: f32,
+    pub curvature: f32,
+    pub vignette: f32,
+}
+
+// Software CRT effect rendered on the CPU, applied on top of the filtered
+// image so that it doesn't need any GPU support
+pub struct Crt {
+    settings: CrtSettings,
+    pub enabled: bool,
+    glow: Vec<[f32; 3]>,
+    output: Image,
+}
+
+impl Crt {
+    pub fn new(settings: CrtSettings, enabled: bool) -> Crt {
+        Crt {
+            settings,
+            enabled,
+            glow: Vec::new(),
+            output: Image::new(0, 0),
+        }
+    }
+
+    pub fn apply(&mut self, input: &Image) -> &Image {
+        let factor = (OUTPUT_WIDTH / input.width).max(1);
+        let (width, height) = (input.width * factor, input.height * factor);
+        if self.output.width != width || self.output.height != height {
+            self.output = Image::new(width, height);
+        }
+        self.blur(input);
+
+        let curvature = self.settings.curvature * 0.25;
+        for oy in 0..height {
+            for ox in 0..width {
+                // Barrel distortion in [-1, 1] coordinates
+                let mut u = (ox as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+                let mut v = (oy as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+                (u, v) = (u * (1.0 + curvature * v * v), v * (1.0 + curvature * u * u));
+                if u.abs() >= 1.0 || v.abs() >= 1.0 {
+                    self.output.pixels[oy * width + ox] = Rgb(0, 0, 0);
+                    continue;
+                }
+
+                let sx = (u + 1.0) / 2.0 * input.width as f32;
+                let sy = (v + 1.0) / 2.0 * input.height as f32;
+                let index = sy as usize * input.width + sx as usize;
+                let color = input.pixels[index];
+                let glow = self.glow[index];
+
+                let scanline = 1.0 - self.settings.scanlines * (1.0 - (PI * sy.fract()).sin());
+                let vignette = (1.0 - self.settings.vignette * (u * u + v * v) / 2.0).max(0.0);
+                let shade = |channel: u8, glow: f32| {
+                    let value = (channel as f32 + self.settings.bloom * glow) * scanline * vignette;
+                    value.clamp(0.0, 255.0) as u8
+                };
+                self.output.pixels[oy * width + ox] = Rgb(
+                    shade(color.0, glow[0]),
+                    shade(color.1, glow[1]),
+                    shade(color.2, glow[2]));
+            }
+        }
+
+        &self.output
+    }
+
+    // 3x3 box blur of the input, added to lit pixels' neighbours as a glow
+    fn blur(&mut self, input: &Image) {
+        self.glow.clear();
+        for y in 0..input.height {
+            for x in 0..input.width {
+                let mut sum = [0.0; 3];
+                for ny in y.saturating_sub(1)..(y + 2).min(input.height) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(input.width) {
+                        let color = input.pixels[ny * input.width + nx];
+                        sum[0] += color.0 as f32 / 9.0;
+                        sum[1] += color.1 as f32 / 9.0;
+                        sum[2] += color.2 as f32 / 9.0;
+                    }
+                }
+                self.glow.push(sum);
+            }
+        }
+    }
+}
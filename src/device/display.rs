@@ -1,104 +1,132 @@
-use sdl2::render::Canvas;
-use sdl2::{TimerSubsystem, VideoSubsystem};
-use sdl2::video::Window;
-use crate::device::sprite::Sprite;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::VideoSubsystem;
+use sdl2::video::{FullscreenType, Window, WindowContext};
+use crate::device::crt::Crt;
+use crate::device::filter::{Filter, Image};
+use crate::device::framebuffer::{Framebuffer, LORES_HEIGHT, LORES_WIDTH};
+use crate::device::palette::{self, Palette};
 use crate::exceptions::Exception;
-
-const WIDTH: u32 = 1920;
-const HEIGHT: u32 = 960;
-const SCALE: u32 = 30;
+use crate::exceptions::ExceptionType::SDL;
+use crate::options::Options;
 
 pub struct Display {
-    pixel: i16,
-    content: [[i16; 64]; 32],
     canvas: Canvas<Window>,
-    timer: TimerSubsystem,
-    modified: i8,
+    texture_creator: TextureCreator<WindowContext>,
+    texture: Texture,
+    texture_size: (usize, usize),
+    logical_size: (usize, usize),
+    palettes: Vec<Palette>,
+    palette: usize,
+    filter: Filter,
+    image: Image,
+    crt: Crt,
 }
 
 impl Display {
-    pub fn new(video_subsystem: &VideoSubsystem, timer: TimerSubsystem) -> Self {
-        let window = video_subsystem.window("Chip8", WIDTH, HEIGHT)
+    pub fn new(video_subsystem: &VideoSubsystem, options: &Options) -> Result<Self, Exception> {
+        let mut window = video_subsystem.window(
+            "Chip8",
+            LORES_WIDTH as u32 * options.scale,
+            LORES_HEIGHT as u32 * options.scale)
             .position_centered()
-            .build().unwrap();
+            .resizable()
+            .build().map_err(|_| Exception::new(SDL))?;
+        if options.fullscreen {
+            window.set_fullscreen(FullscreenType::Desktop).map_err(|_| Exception::new(SDL))?;
+        }
+
+        let mut canvas = window.into_canvas().build().map_err(|_| Exception::new(SDL))?;
+        // The logical size letterboxes the picture and keeps its aspect ratio
+        // whatever the window size is
+        canvas.set_logical_size(LORES_WIDTH as u32, LORES_HEIGHT as u32)
+            .map_err(|_| Exception::new(SDL))?;
+        canvas.set_integer_scale(options.integer_scale).map_err(|_| Exception::new(SDL))?;
+
+        let texture_creator = canvas.texture_creator();
+        let texture = texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, LORES_WIDTH as u32, LORES_HEIGHT as u32)
+            .map_err(|_| Exception::new(SDL))?;
 
-        let canvas = window.into_canvas().build().unwrap();
+        let (palettes, palette) = palette::palettes(options)?;
 
-        Display {
-            pixel: 30,
-            content: [[0; 64]; 32],
+        Ok(Display {
             canvas,
-            timer,
-            modified: 0,
-        }
+            texture_creator,
+            texture,
+            texture_size: (LORES_WIDTH, LORES_HEIGHT),
+            logical_size: (LORES_WIDTH, LORES_HEIGHT),
+            palettes,
+            palette,
+            filter: Filter::new(options.filter, options.filter_frames),
+            image: Image::new(LORES_WIDTH, LORES_HEIGHT),
+            crt: Crt::new(options.crt_settings, options.crt),
+        })
     }
 
-    pub fn update(&mut self) -> Result<(), Exception> {
-        if self.modified == 0 {
-            return Ok(());
-        }
-        for y in 0..32 {
-            for x in 0..64 {
-                self.draw_pixel(x as u8, y as u8, self.content[y][x] as u8);
-            }
-        }
-        self.canvas.present();
-        self.timer.delay(1000 / 60);
-        self.modified = 0;
-        Ok(())
+    pub fn palette(&self) -> &Palette {
+        &self.palettes[self.palette]
     }
 
-    pub fn clear(&mut self) -> Result<(), Exception> {
-        self.canvas.clear();
-        for y in 0..32 {
-            for x in 0..64 {
-                self.content[y][x] = 0;
-            }
-        }
-        self.update()
+    // Last image rendered, before the CRT filter
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn cycle_palette(&mut self) {
+        self.palette = (self.palette + 1) % self.palettes.len();
+        println!("Palette: {}", self.palettes[self.palette].name);
     }
 
-    pub fn draw(&mut self, sprite: &Sprite, x: u8, y: u8, vf: &mut u8) -> Result<(), Exception> {
-        let x_pos: u32 = x as u32 % (WIDTH / SCALE);
-        let y_pos: u32 = y as u32 % (HEIGHT / SCALE);
-
-        *vf = 0;
-
-        for line_count in 0..sprite.length() {
-            for column_count in 0..8 {
-                if x_pos + column_count < WIDTH / SCALE &&
-                    y_pos + (line_count as u32) < HEIGHT / SCALE {
-                    let pixel_value: bool = match sprite.get(line_count) {
-                        Ok(value) => value & (0x80 >> column_count) != 0,
-                        Err(e) => return Err(e),
-                    };
-                    let x_pos_new: usize = ((x_pos + column_count) % (WIDTH / SCALE)) as usize;
-                    let y_pos_new: usize = ((y_pos + line_count as u32) % (HEIGHT / SCALE)) as usize;
-                    let old_pixel: i16 = self.content[y_pos_new][x_pos_new];
-
-                    if pixel_value && (old_pixel == 1) {
-                        self.content[y_pos_new][x_pos_new] = 0;
-                        *vf = 1;
-                        self.modified = 1;
-                    } else if pixel_value {
-                        self.content[y_pos_new][x_pos_new] = 1;
-                        self.modified = 1;
-                    }
+    pub fn toggle_fullscreen(&mut self) -> Result<(), Exception> {
+        let window = self.canvas.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        window.set_fullscreen(fullscreen).map_err(|_| Exception::new(SDL))
+    }
+
+    pub fn toggle_crt(&mut self) {
+        self.crt.enabled = !self.crt.enabled;
+    }
+
+    // Upload the framebuffer to the texture and present it, once per frame
+    pub fn render(&mut self, framebuffer: &Framebuffer) -> Result<(), Exception> {
+        let size = (framebuffer.width(), framebuffer.height());
+        if size != self.logical_size {
+            self.canvas.set_logical_size(size.0 as u32, size.1 as u32)
+                .map_err(|_| Exception::new(SDL))?;
+            self.logical_size = size;
+        }
+
+        self.filter.apply(framebuffer, &self.palettes[self.palette], &mut self.image);
+        let image = if self.crt.enabled { self.crt.apply(&self.image) } else { &self.image };
+
+        if (image.width, image.height) != self.texture_size {
+            let texture = self.texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, image.width as u32, image.height as u32)
+                .map_err(|_| Exception::new(SDL))?;
+            // Textures are not freed on drop with the unsafe_textures feature
+            unsafe { std::mem::replace(&mut self.texture, texture).destroy() };
+            self.texture_size = (image.width, image.height);
+        }
+
+        self.texture.with_lock(None, |buffer: &mut [u8], pitch: usize| {
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    let offset = y * pitch + x * 3;
+                    let color = image.pixels[y * image.width + x];
+                    buffer[offset] = color.0;
+                    buffer[offset + 1] = color.1;
+                    buffer[offset + 2] = color.2;
                 }
             }
-        }
-        self.update()
-    }
+        }).map_err(|_| Exception::new(SDL))?;
 
-    fn draw_pixel(&mut self, x: u8, y: u8, value: u8) {
-        let color = if value == 0 { 0 } else { 255 };
-        let pixel = sdl2::rect::Rect::new(
-            x as i32 * self.pixel as i32,
-            y as i32 * self.pixel as i32,
-            self.pixel as u32,
-            self.pixel as u32,
-        );
-        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(0, color, 0));
-        self.canvas.fill_rect(pixel).unwrap();
+        self.canvas.clear();
+        self.canvas.copy(&self.texture, None, None).map_err(|_| Exception::new(SDL))?;
+        self.canvas.present();
+        Ok(())
     }
-}
\ No newline at end of file
+}
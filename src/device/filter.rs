@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use crate::device::framebuffer::Framebuffer;
+use crate::device::palette::{Palette, Rgb};
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::BadArgument;
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![Rgb(0, 0, 0); width * height],
+        }
+    }
+
+    pub fn from_framebuffer(framebuffer: &Framebuffer, palette: &Palette) -> Image {
+        let mut image = Image::new(framebuffer.width(), framebuffer.height());
+        for y in 0..image.height {
+            for x in 0..image.width {
+                image.pixels[y * image.width + x] = palette.color(framebuffer.pixel(x, y));
+            }
+        }
+        image
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterMode {
+    Off,
+    // Pixels fade out over a number of frames once turned off
+    Phosphor,
+    // Average of the last two frames
+    Blend,
+    // A pixel stays lit if it was lit in one of the last frames
+    Max,
+}
+
+impl FromStr for FilterMode {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<FilterMode, Exception> {
+        match s {
+            "off" => Ok(FilterMode::Off),
+            "phosphor" => Ok(FilterMode::Phosphor),
+            "blend" => Ok(FilterMode::Blend),
+            "max" => Ok(FilterMode::Max),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+// Anti-flicker filters turning the framebuffer into the presented image. The
+// framebuffer itself is never modified.
+pub struct Filter {
+    mode: FilterMode,
+    frames: usize,
+    history: VecDeque<Framebuffer>,
+    levels: Vec<f32>,
+    lit: Vec<Rgb>,
+}
+
+impl Filter {
+    pub fn new(mode: FilterMode, frames: usize) -> Filter {
+        Filter {
+            mode,
+            frames: frames.max(1),
+            history: VecDeque::new(),
+            levels: Vec::new(),
+            lit: Vec::new(),
+        }
+    }
+
+    pub fn apply(&mut self, framebuffer: &Framebuffer, palette: &Palette, image: &mut Image) {
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        // The image may already have the right size while the filter's own
+        // buffers are still empty
+        if image.width != width || image.height != height || self.levels.len() != width * height {
+            *image = Image::new(width, height);
+            self.history.clear();
+            self.levels = vec![0.0; width * height];
+            self.lit = vec![palette.color(0); width * height];
+        }
+
+        match self.mode {
+            FilterMode::Off => {
+                for y in 0..height {
+                    for x in 0..width {
+                        image.pixels[y * width + x] = palette.color(framebuffer.pixel(x, y));
+                    }
+                }
+            }
+            FilterMode::Phosphor => {
+                let background = palette.color(0);
+                for y in 0..height {
+                    for x in 0..width {
+                        let index = y * width + x;
+                        let pixel = framebuffer.pixel(x, y);
+                        if pixel != 0 {
+                            self.levels[index] = 1.0;
+                            self.lit[index] = palette.color(pixel);
+                            image.pixels[index] = self.lit[index];
+                        } else {
+                            self.levels[index] = (self.levels[index] - 1.0 / self.frames as f32).max(0.0);
+                            image.pixels[index] = mix(background, self.lit[index], self.levels[index]);
+                        }
+                    }
+                }
+            }
+            FilterMode::Blend => {
+                let previous = self.history.back().unwrap_or(framebuffer);
+                for y in 0..height {
+                    for x in 0..width {
+                        image.pixels[y * width + x] = mix(
+                            palette.color(framebuffer.pixel(x, y)),
+                            palette.color(previous.pixel(x, y)),
+                            0.5);
+                    }
+                }
+                self.push(framebuffer, 1);
+            }
+            FilterMode::Max => {
+                self.push(framebuffer, self.frames);
+                for y in 0..height {
+                    for x in 0..width {
+                        let pixel = self.history.iter().fold(0, |pixel, frame| pixel | frame.pixel(x, y));
+                        image.pixels[y * width + x] = palette.color(pixel);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, framebuffer: &Framebuffer, frames: usize) {
+        self.history.push_back(framebuffer.clone());
+        while self.history.len() > frames {
+            self.history.pop_front();
+        }
+    }
+}
+
+// Linear interpolation from `from` (at 0) to `to` (at 1)
+fn mix(from: Rgb, to: Rgb, amount: f32) -> Rgb {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+    Rgb(channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::palette::BUILTIN;
+    use crate::device::sprite::Sprite;
+
+    #[test]
+    fn phosphor_fades_at_lores() {
+        let palette = &BUILTIN[0];
+        let mut framebuffer = Framebuffer::new();
+        framebuffer.draw(&Sprite::new_with_content(vec![0x80]), 0, 0).unwrap();
+        let mut filter = Filter::new(FilterMode::Phosphor, 4);
+        let mut image = Image::new(64, 32);
+
+        filter.apply(&framebuffer, palette, &mut image);
+        assert_eq!(image.pixels[0], palette.color(1));
+        assert_eq!(image.pixels[1], palette.color(0));
+
+        framebuffer.clear();
+        filter.apply(&framebuffer, palette, &mut image);
+        assert_eq!(image.pixels[0], mix(palette.color(0), palette.color(1), 0.75));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::device::sprite::Sprite;
+use crate::exceptions::Exception;
+
+pub const LORES_WIDTH: usize = 64;
+pub const LORES_HEIGHT: usize = 32;
+pub const MAX_WIDTH: usize = 128;
+pub const MAX_HEIGHT: usize = 64;
+pub const PLANES: usize = 2;
+
+// Each row is packed in a u128, pixel x being bit (127 - x), so that a sprite
+// byte can be put in place with a single shift.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Framebuffer {
+    planes: [[u128; MAX_HEIGHT]; PLANES],
+    width: usize,
+    height: usize,
+    selected_planes: u8,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Framebuffer {
+        Framebuffer::new()
+    }
+}
+
+impl Framebuffer {
+    pub fn new() -> Framebuffer {
+        Framebuffer {
+            planes: [[0; MAX_HEIGHT]; PLANES],
+            width: LORES_WIDTH,
+            height: LORES_HEIGHT,
+            selected_planes: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Returns the planes lit at (x, y) as a bitmask, bit 0 being the first plane
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let mut value = 0;
+        for plane in 0..PLANES {
+            if self.planes[plane][y] & (1 << (127 - x)) != 0 {
+                value |= 1 << plane;
+            }
+        }
+        value
+    }
+
+    pub fn clear(&mut self) {
+        for plane in 0..PLANES {
+            if self.selected_planes & (1 << plane) != 0 {
+                self.planes[plane] = [0; MAX_HEIGHT];
+            }
+        }
+    }
+
+    // XOR the sprite at (x, y) in every selected plane and returns whether a
+    // lit pixel has been erased. With several planes selected, the sprite
+    // holds the rows of each plane one after the other.
+    pub fn draw(&mut self, sprite: &Sprite, x: u8, y: u8) -> Result<bool, Exception> {
+        let x_pos = x as usize % self.width;
+        let y_pos = y as usize % self.height;
+        let row_mask = !0u128 << (MAX_WIDTH - self.width);
+        let selected = self.selected_planes.count_ones() as usize;
+        let lines = sprite.length().checked_div(selected).unwrap_or(0);
+
+        let mut collision = false;
+        let mut offset = 0;
+        for plane in 0..PLANES {
+            if self.selected_planes & (1 << plane) == 0 {
+                continue;
+            }
+            for line in 0..lines {
+                let y_new = y_pos + line;
+                if y_new >= self.height {
+                    break;
+                }
+                let bits = (((sprite.get(offset + line)? as u128) << 120) >> x_pos) & row_mask;
+                collision |= self.planes[plane][y_new] & bits != 0;
+                self.planes[plane][y_new] ^= bits;
+            }
+            offset += lines;
+        }
+        Ok(collision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(framebuffer: &Framebuffer, y: usize) -> Vec<usize> {
+        (0..framebuffer.width()).filter(|&x| framebuffer.pixel(x, y) != 0).collect()
+    }
+
+    #[test]
+    fn collision_only_when_erasing() {
+        let mut framebuffer = Framebuffer::new();
+        assert!(!framebuffer.draw(&Sprite::new_with_content(vec![0xF0]), 0, 0).unwrap());
+        // Next to the lit pixels without covering them
+        assert!(!framebuffer.draw(&Sprite::new_with_content(vec![0x0F]), 0, 0).unwrap());
+        assert_eq!(lit(&framebuffer, 0), (0..8).collect::<Vec<usize>>());
+
+        assert!(framebuffer.draw(&Sprite::new_with_content(vec![0x80]), 0, 0).unwrap());
+        assert_eq!(lit(&framebuffer, 0), (1..8).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn clips_at_the_edges() {
+        let mut framebuffer = Framebuffer::new();
+        assert!(!framebuffer.draw(&Sprite::new_with_content(vec![0xFF; 4]), 60, 30).unwrap());
+        assert_eq!(lit(&framebuffer, 30), vec![60, 61, 62, 63]);
+        assert_eq!(lit(&framebuffer, 31), vec![60, 61, 62, 63]);
+        for y in 0..2 {
+            assert!(lit(&framebuffer, y).is_empty());
+        }
+    }
+
+    #[test]
+    fn wraps_the_start_coordinates() {
+        let mut framebuffer = Framebuffer::new();
+        framebuffer.draw(&Sprite::new_with_content(vec![0x80]), 64 + 3, 32 + 2).unwrap();
+        assert_eq!(lit(&framebuffer, 2), vec![3]);
+    }
+}
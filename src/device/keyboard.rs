@@ -1,21 +1,25 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
 use crate::exceptions::{Exception};
 use crate::exceptions::ExceptionType::SDL;
 
 pub struct Keyboard {
-    map: [Keycode; 16],
+    map: [char; 16],
     pressed_keys: [u8; 16],
 }
 
+impl Default for Keyboard {
+    fn default() -> Keyboard {
+        Keyboard::new()
+    }
+}
+
 impl Keyboard {
     pub fn new() -> Keyboard {
         let key_map = [
-            Keycode::X, Keycode::Num1, Keycode::Num2,
-            Keycode::Num3, Keycode::A, Keycode::Z,
-            Keycode::E, Keycode::Q, Keycode::S,
-            Keycode::D, Keycode::W, Keycode::C,
-            Keycode::Num4, Keycode::R, Keycode::F, Keycode::V
+            'x', '1', '2',
+            '3', 'a', 'z',
+            'e', 'q', 's',
+            'd', 'w', 'c',
+            '4', 'r', 'f', 'v'
         ];
         Keyboard { map: key_map, pressed_keys: [0; 16] }
     }
@@ -40,19 +44,17 @@ impl Keyboard {
         Ok(key)
     }
 
-    pub fn handle_event(&mut self, event: Event) {
-        match event {
-            Event::KeyDown { keycode: Some(keycode), .. } => {
-                if let Some(chip8_key) = self.map.iter().position(|&k| k == keycode) {
-                    self.pressed_keys[chip8_key] = 1;
-                }
-            }
-            Event::KeyUp { keycode: Some(keycode), .. } => {
-                if let Some(chip8_key) = self.map.iter().position(|&k| k == keycode) {
-                    self.pressed_keys[chip8_key] = 0;
-                }
-            }
-            _ => {}
-        }
+    // Returns the CHIP-8 key bound to a character of the host keyboard
+    pub fn key_for(&self, c: char) -> Option<u8> {
+        let c = c.to_ascii_lowercase();
+        self.map.iter().position(|&k| k == c).map(|key| key as u8)
     }
-}
\ No newline at end of file
+
+    pub fn press(&mut self, key: u8) {
+        self.pressed_keys[key as usize] = 1;
+    }
+
+    pub fn release(&mut self, key: u8) {
+        self.pressed_keys[key as usize] = 0;
+    }
+}
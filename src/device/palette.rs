@@ -0,0 +1,75 @@
+use std::str::FromStr;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::BadArgument;
+use crate::options::Options;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl FromStr for Rgb {
+    type Err = Exception;
+
+    // Parses `RRGGBB`, with or without a leading `#`
+    fn from_str(s: &str) -> Result<Rgb, Exception> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 {
+            return Err(Exception::new(BadArgument));
+        }
+        let value = u32::from_str_radix(hex, 16).map_err(|_| Exception::new(BadArgument))?;
+        Ok(Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+    }
+}
+
+// Colors are indexed by the planes lit in a pixel: background, first plane,
+// second plane, both planes
+#[derive(Clone)]
+pub struct Palette {
+    pub name: &'static str,
+    pub colors: [Rgb; 4],
+}
+
+pub const BUILTIN: [Palette; 5] = [
+    Palette {
+        name: "green",
+        colors: [Rgb(0x00, 0x00, 0x00), Rgb(0x00, 0xFF, 0x00), Rgb(0x00, 0x80, 0x00), Rgb(0x80, 0xFF, 0x80)],
+    },
+    Palette {
+        name: "amber",
+        colors: [Rgb(0x00, 0x00, 0x00), Rgb(0xFF, 0xB0, 0x00), Rgb(0x80, 0x58, 0x00), Rgb(0xFF, 0xD8, 0x70)],
+    },
+    Palette {
+        name: "white",
+        colors: [Rgb(0x00, 0x00, 0x00), Rgb(0xFF, 0xFF, 0xFF), Rgb(0x80, 0x80, 0x80), Rgb(0xC0, 0xC0, 0xC0)],
+    },
+    Palette {
+        name: "octo",
+        colors: [Rgb(0x99, 0x66, 0x00), Rgb(0xFF, 0xCC, 0x00), Rgb(0xFF, 0x66, 0x00), Rgb(0x66, 0x22, 0x00)],
+    },
+    Palette {
+        name: "gameboy",
+        colors: [Rgb(0x9B, 0xBC, 0x0F), Rgb(0x0F, 0x38, 0x0F), Rgb(0x8B, 0xAC, 0x0F), Rgb(0x30, 0x62, 0x30)],
+    },
+];
+
+impl Palette {
+    pub fn color(&self, pixel: u8) -> Rgb {
+        self.colors[(pixel & 3) as usize]
+    }
+}
+
+// Builds the list of palettes to cycle through and the index of the one
+// selected in the options, with its colors overridden by the custom ones
+pub fn palettes(options: &Options) -> Result<(Vec<Palette>, usize), Exception> {
+    let mut palettes = BUILTIN.to_vec();
+    let index = palettes.iter().position(|palette| palette.name == options.palette)
+        .ok_or(Exception::new(BadArgument))?;
+
+    let custom = [options.background, options.foreground, options.foreground2, options.blend];
+    for (color, value) in palettes[index].colors.iter_mut().zip(custom) {
+        if let Some(value) = value {
+            *color = value;
+        }
+    }
+
+    Ok((palettes, index))
+}
@@ -1,54 +1,264 @@
-use sdl2::audio::{AudioCallback, AudioDevice};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::str::FromStr;
+use std::path::Path;
+use sdl2::audio::AudioCallback;
+use crate::device::speaker::file::FileAudio;
+use crate::device::speaker::null::NullAudio;
+use crate::device::speaker::sdl::SdlAudio;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::BadArgument;
+use crate::options::Options;
 
-struct SquareWave {
-    phase_inc: f32,
+mod file;
+mod null;
+mod sdl;
+
+// Time taken by the tone to fade in and out, which avoids the clicks of
+// starting and stopping the wave abruptly
+const ENVELOPE_SECONDS: f32 = 0.005;
+// Frames buffered ahead of the audio output, beyond which the oldest ones are
+// dropped to keep the latency low
+const MAX_QUEUED_FRAMES: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Sine,
+    Sawtooth,
+    Noise,
+}
+
+impl FromStr for Waveform {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<Waveform, Exception> {
+        match s {
+            "square" => Ok(Waveform::Square),
+            "triangle" => Ok(Waveform::Triangle),
+            "sine" => Ok(Waveform::Sine),
+            "sawtooth" => Ok(Waveform::Sawtooth),
+            "noise" => Ok(Waveform::Noise),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ToneSettings {
+    pub pitch: f32,
+    pub volume: f32,
+    pub waveform: Waveform,
+}
+
+// Buzzer tone generator, sounding while its gate is open
+pub struct Tone {
+    settings: ToneSettings,
+    sample_rate: f32,
     phase: f32,
-    volume: f32,
+    noise: u16,
+    level: f32,
+    pub gate: bool,
+    pub muted: bool,
 }
 
-impl AudioCallback for SquareWave {
-    type Channel = u8;
+impl Tone {
+    pub fn new(settings: ToneSettings, sample_rate: i32) -> Tone {
+        Tone {
+            settings,
+            sample_rate: sample_rate as f32,
+            phase: 0.0,
+            noise: 1,
+            level: 0.0,
+            gate: false,
+            muted: false,
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.settings.volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn fill(&mut self, out: &mut [f32]) {
+        let phase_inc = self.settings.pitch / self.sample_rate;
+        let envelope_step = 1.0 / (ENVELOPE_SECONDS * self.sample_rate);
+        let target = if self.gate && !self.muted { 1.0 } else { 0.0 };
 
-    fn callback(&mut self, out: &mut [u8]) {
         for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
-                (self.volume * 255.0) as u8
+            self.level = if self.level < target {
+                (self.level + envelope_step).min(target)
             } else {
-                0
+                (self.level - envelope_step).max(target)
             };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
+            *x = self.sample() * self.level * self.settings.volume;
+
+            let phase = self.phase + phase_inc;
+            // The noise changes 8 times per period so that the pitch sets its color
+            if (phase * 8.0) as u32 != (self.phase * 8.0) as u32 {
+                let bit = (self.noise ^ (self.noise >> 1)) & 1;
+                self.noise = (self.noise >> 1) | (bit << 14);
+            }
+            self.phase = phase % 1.0;
+        }
+    }
+
+    fn sample(&self) -> f32 {
+        match self.settings.waveform {
+            Waveform::Square => if self.phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Triangle => 4.0 * (self.phase - 0.5).abs() - 1.0,
+            Waveform::Sine => (2.0 * PI * self.phase).sin(),
+            Waveform::Sawtooth => 2.0 * self.phase - 1.0,
+            Waveform::Noise => if self.noise & 1 == 0 { 1.0 } else { -1.0 },
         }
     }
 }
 
+// Plays the buzzer gates pushed by the emulator, one per frame, each lasting
+// exactly a 60th of a second of samples whatever the output buffer size is
+pub struct ToneStream {
+    tone: Tone,
+    gates: VecDeque<bool>,
+    samples_per_frame: f64,
+    frame_samples: f64,
+}
+
+impl ToneStream {
+    pub fn new(settings: ToneSettings, sample_rate: i32) -> ToneStream {
+        ToneStream {
+            tone: Tone::new(settings, sample_rate),
+            gates: VecDeque::new(),
+            samples_per_frame: sample_rate as f64 / 60.0,
+            frame_samples: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, gate: bool) {
+        self.gates.push_back(gate);
+        while self.gates.len() > MAX_QUEUED_FRAMES {
+            self.gates.pop_front();
+        }
+    }
+
+    pub fn queued(&self) -> usize {
+        self.gates.len()
+    }
+
+    pub fn configure(&mut self, volume: f32, muted: bool) {
+        self.tone.set_volume(volume);
+        self.tone.muted = muted;
+    }
+
+    pub fn fill(&mut self, out: &mut [f32]) {
+        let mut offset = 0;
+        while offset < out.len() {
+            if self.frame_samples < 1.0 {
+                // Silence when the emulator falls behind
+                self.tone.gate = self.gates.pop_front().unwrap_or(false);
+                self.frame_samples += self.samples_per_frame;
+            }
+            let count = (self.frame_samples as usize).min(out.len() - offset);
+            self.tone.fill(&mut out[offset..offset + count]);
+            self.frame_samples -= count as f64;
+            offset += count;
+        }
+    }
+}
+
+impl AudioCallback for ToneStream {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        self.fill(out);
+    }
+}
+
+// Where the buzzer output goes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioOutput {
+    Sdl,
+    Null,
+    File,
+}
+
+impl FromStr for AudioOutput {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<AudioOutput, Exception> {
+        match s {
+            "sdl" => Ok(AudioOutput::Sdl),
+            "null" => Ok(AudioOutput::Null),
+            "file" => Ok(AudioOutput::File),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+pub trait AudioBackend {
+    fn push_frame(&mut self, gate: bool) -> Result<(), Exception>;
+
+    // Frames pushed but not played yet, or None if the backend doesn't play
+    // in real time
+    fn queued_frames(&mut self) -> Option<usize>;
+
+    fn configure(&mut self, volume: f32, muted: bool);
+
+    fn close(&mut self) -> Result<(), Exception> {
+        Ok(())
+    }
+}
+
 pub struct Speaker {
-    device: AudioDevice<SquareWave>,
+    backend: Box<dyn AudioBackend>,
+    volume: f32,
+    muted: bool,
 }
 
 impl Speaker {
-    pub(crate) fn new(audio_subsystem: &sdl2::AudioSubsystem) -> Speaker {
-        let device = audio_subsystem.open_playback(None, &sdl2::audio::AudioSpecDesired {
-            freq: Some(44100),
-            channels: Some(1),
-            samples: None,
-        }, |spec| {
-            SquareWave {
-                phase_inc: 440.0 / spec.freq as f32,
-                phase: 0.0,
-                volume: 0.25,
+    pub(crate) fn new(options: &Options, sdl_context: Option<&sdl2::Sdl>) -> Result<Speaker, Exception> {
+        let backend: Box<dyn AudioBackend> = match options.audio {
+            AudioOutput::Null => Box::new(NullAudio),
+            AudioOutput::File => {
+                let path = options.wav.as_ref().ok_or(Exception::new(BadArgument))?;
+                Box::new(FileAudio::create(Path::new(path), options.sample_rate, options.tone)?)
             }
-        }).unwrap();
+            AudioOutput::Sdl => match sdl_context.map(|sdl| SdlAudio::open(sdl, options.tone)) {
+                Some(Ok(sdl_audio)) => Box::new(sdl_audio),
+                _ => {
+                    eprintln!("Warning: no audio device available, the sound is disabled");
+                    Box::new(NullAudio)
+                }
+            },
+        };
 
-        Speaker {
-            device
-        }
+        Ok(Speaker {
+            backend,
+            volume: options.tone.volume,
+            muted: false,
+        })
+    }
+
+    pub fn push_frame(&mut self, gate: bool) -> Result<(), Exception> {
+        self.backend.push_frame(gate)
+    }
+
+    pub fn queued_frames(&mut self) -> Option<usize> {
+        self.backend.queued_frames()
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.backend.configure(self.volume, self.muted);
+        println!("Sound {}", if self.muted { "muted" } else { "unmuted" });
     }
 
-    pub fn on(&self) {
-        self.device.resume();
+    pub fn change_volume(&mut self, delta: f32) {
+        self.volume = (self.volume + delta).clamp(0.0, 1.0);
+        self.backend.configure(self.volume, self.muted);
+        println!("Volume: {:.0}%", self.volume * 100.0);
     }
 
-    pub fn off(&self) {
-        self.device.pause();
+    pub fn close(&mut self) -> Result<(), Exception> {
+        self.backend.close()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+use crate::capture::audio::WavExport;
+use crate::device::speaker::{AudioBackend, ToneSettings};
+use crate::exceptions::Exception;
+
+// Records the sound to a WAV file instead of playing it
+pub struct FileAudio {
+    export: Option<WavExport>,
+}
+
+impl FileAudio {
+    pub fn create(path: &Path, sample_rate: u32, settings: ToneSettings) -> Result<FileAudio, Exception> {
+        Ok(FileAudio {
+            export: Some(WavExport::create(path, sample_rate, settings)?),
+        })
+    }
+}
+
+impl AudioBackend for FileAudio {
+    fn push_frame(&mut self, gate: bool) -> Result<(), Exception> {
+        match &mut self.export {
+            Some(export) => export.add_frame(gate),
+            None => Ok(()),
+        }
+    }
+
+    fn queued_frames(&mut self) -> Option<usize> {
+        None
+    }
+
+    fn configure(&mut self, volume: f32, muted: bool) {
+        if let Some(export) = &mut self.export {
+            export.configure(volume, muted);
+        }
+    }
+
+    fn close(&mut self) -> Result<(), Exception> {
+        if let Some(export) = self.export.take() {
+            let path = export.path().to_path_buf();
+            export.finish()?;
+            println!("Audio saved to {}", path.display());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+use crate::device::speaker::AudioBackend;
+use crate::exceptions::Exception;
+
+// Discards the sound, for machines without an audio device
+pub struct NullAudio;
+
+impl AudioBackend for NullAudio {
+    fn push_frame(&mut self, _gate: bool) -> Result<(), Exception> {
+        Ok(())
+    }
+
+    fn queued_frames(&mut self) -> Option<usize> {
+        None
+    }
+
+    fn configure(&mut self, _volume: f32, _muted: bool) {}
+}
@@ -0,0 +1,42 @@
+use sdl2::audio::AudioDevice;
+use crate::device::speaker::{AudioBackend, ToneSettings, ToneStream};
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::SDL;
+
+// The device keeps playing all the time, fed with the frames' buzzer gates
+pub struct SdlAudio {
+    device: AudioDevice<ToneStream>,
+}
+
+impl SdlAudio {
+    pub fn open(sdl_context: &sdl2::Sdl, settings: ToneSettings) -> Result<SdlAudio, Exception> {
+        let audio_subsystem = sdl_context.audio().map_err(|_| Exception::new(SDL))?;
+        let device = audio_subsystem.open_playback(None, &sdl2::audio::AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        }, |spec| {
+            ToneStream::new(settings, spec.freq)
+        }).map_err(|_| Exception::new(SDL))?;
+        device.resume();
+
+        Ok(SdlAudio {
+            device
+        })
+    }
+}
+
+impl AudioBackend for SdlAudio {
+    fn push_frame(&mut self, gate: bool) -> Result<(), Exception> {
+        self.device.lock().push(gate);
+        Ok(())
+    }
+
+    fn queued_frames(&mut self) -> Option<usize> {
+        Some(self.device.lock().queued())
+    }
+
+    fn configure(&mut self, volume: f32, muted: bool) {
+        self.device.lock().configure(volume, muted);
+    }
+}
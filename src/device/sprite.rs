@@ -17,7 +17,7 @@ impl Sprite {
         }
     }
 
-    pub(crate) fn new_with_content(p0: Vec<u8>) -> Sprite {
+    pub fn new_with_content(p0: Vec<u8>) -> Sprite {
         Sprite {
             length: p0.len(),
             cap: p0.len(),
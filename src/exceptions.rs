@@ -3,30 +3,49 @@ use std::fmt;
 #[derive(Debug)]
 pub enum ExceptionType {
     AddressOutOfRange,
+    ReservedWrite,
+    RomTooLarge { size: usize, space: usize },
     StackOverflow,
-    StackPointerOutOfRange,
+    StackUnderflow,
     SDL,
+    Terminal,
+    File,
     BadArgument,
     BadInstruction,
-    Other
 }
 
 #[derive(Debug)]
 pub struct Exception {
     exception_type: ExceptionType,
+    // Address of the instruction which raised it
+    address: Option<u16>,
 }
 
 impl Exception {
-    pub(crate) fn new(p0: ExceptionType) -> Exception {
+    pub fn new(p0: ExceptionType) -> Exception {
         Exception {
             exception_type: p0,
+            address: None,
         }
     }
+
+    // Keeps the address already set by a nested call
+    pub fn at(mut self, address: u16) -> Exception {
+        self.address.get_or_insert(address);
+        self
+    }
 }
 
 impl fmt::Display for Exception {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Exception: {:?}",
-               self.exception_type)
+        match self.exception_type {
+            ExceptionType::RomTooLarge { size, space } =>
+                write!(f, "Exception: RomTooLarge, the ROM is {} bytes but only {} fit in the memory", size, space)?,
+            _ => write!(f, "Exception: {:?}", self.exception_type)?,
+        }
+        if let Some(address) = self.address {
+            write!(f, " at {:03X}", address)?;
+        }
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use crate::capture::recorder::Recorder;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::BadArgument;
+
+pub(crate) mod sdl;
+pub(crate) mod tui;
+pub(crate) mod headless;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Frontend {
+    Sdl,
+    Tui,
+    Headless,
+}
+
+impl FromStr for Frontend {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<Frontend, Exception> {
+        match s {
+            "sdl" => Ok(Frontend::Sdl),
+            "tui" => Ok(Frontend::Tui),
+            "headless" => Ok(Frontend::Headless),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+// Clock the emulation speed follows
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SyncMode {
+    Video,
+    Audio,
+}
+
+impl FromStr for SyncMode {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<SyncMode, Exception> {
+        match s {
+            "video" => Ok(SyncMode::Video),
+            "audio" => Ok(SyncMode::Audio),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+// Keeps the frontends' main loops at 60 frames per second
+pub struct FramePacer {
+    frame_time: Duration,
+    next_frame: Instant,
+}
+
+impl FramePacer {
+    pub fn new() -> FramePacer {
+        let frame_time = Duration::from_micros(1_000_000 / 60);
+        FramePacer {
+            frame_time,
+            next_frame: Instant::now() + frame_time,
+        }
+    }
+
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        if now < self.next_frame {
+            std::thread::sleep(self.next_frame - now);
+            self.next_frame += self.frame_time;
+        } else {
+            self.next_frame = now + self.frame_time;
+        }
+    }
+}
+
+// Finishes the file and tells where it was saved
+pub fn stop_recording(recorder: Recorder) -> Result<(), Exception> {
+    let path = recorder.path().to_path_buf();
+    recorder.finish()?;
+    println!("Recording saved to {}", path.display());
+    Ok(())
+}
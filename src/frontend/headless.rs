@@ -0,0 +1,65 @@
+use std::path::Path;
+use crate::capture::recorder::Recorder;
+use crate::capture::Screenshots;
+use crate::chip8::Chip8;
+use crate::device::filter::Image;
+use crate::device::palette::{self, Palette};
+use crate::device::speaker::Speaker;
+use crate::exceptions::Exception;
+use crate::frontend::stop_recording;
+use crate::options::Options;
+
+// Runs a fixed number of frames as fast as possible, without any window or
+// audio device, for batch runs and CI
+pub struct HeadlessFrontend {
+    frames: u64,
+    palette: Palette,
+    screenshots: Option<Screenshots>,
+    recorder: Option<Recorder>,
+    speaker: Speaker,
+}
+
+impl HeadlessFrontend {
+    pub fn new(options: &Options) -> Result<HeadlessFrontend, Exception> {
+        let (palettes, index) = palette::palettes(options)?;
+
+        Ok(HeadlessFrontend {
+            frames: options.frames.unwrap_or(600),
+            palette: palettes[index].clone(),
+            screenshots: options.screenshot.then(|| Screenshots::new(options)),
+            recorder: match &options.record {
+                Some(path) => Some(Recorder::start(Path::new(path), options.record_scale, options.tone, None)?),
+                None => None,
+            },
+            speaker: Speaker::new(options, None)?,
+        })
+    }
+
+    pub fn run(&mut self, chip8: &mut Chip8) -> Result<(), Exception> {
+        let result = self.run_frames(chip8);
+
+        // Also after a crash, so that the WAV file and the recording stay readable
+        let mut result = result.and(self.speaker.close());
+        if let Some(recorder) = self.recorder.take() {
+            result = result.and(stop_recording(recorder));
+        }
+        result?;
+
+        if let Some(screenshots) = &self.screenshots {
+            let path = screenshots.save(&chip8.framebuffer(), &self.palette, chip8.frame())?;
+            println!("Screenshot saved to {}", path.display());
+        }
+        Ok(())
+    }
+
+    fn run_frames(&mut self, chip8: &mut Chip8) -> Result<(), Exception> {
+        for _ in 0..self.frames {
+            let buzzing = chip8.run_frame()?;
+            if let Some(recorder) = &mut self.recorder {
+                recorder.add_frame(&Image::from_framebuffer(&chip8.framebuffer(), &self.palette), buzzing)?;
+            }
+            self.speaker.push_frame(buzzing)?;
+        }
+        Ok(())
+    }
+}
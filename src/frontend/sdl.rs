@@ -0,0 +1,158 @@
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use std::path::Path;
+use std::time::Duration;
+use crate::capture::recorder::Recorder;
+use crate::capture::{Recordings, Screenshots};
+use crate::chip8::Chip8;
+use crate::device::display::Display;
+use crate::device::keyboard::Keyboard;
+use crate::device::speaker::Speaker;
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::SDL;
+use crate::frontend::{FramePacer, SyncMode, stop_recording};
+use crate::options::Options;
+
+// Frames kept ahead of the audio output when the emulation follows its clock
+const AUDIO_QUEUED_FRAMES: usize = 2;
+
+pub struct SdlFrontend {
+    display: Display,
+    speaker: Speaker,
+    screenshots: Screenshots,
+    recordings: Recordings,
+    recorder: Option<Recorder>,
+    sync: SyncMode,
+
+    sdl_context: sdl2::Sdl,
+}
+
+impl SdlFrontend {
+    pub fn new(options: &Options) -> Result<SdlFrontend, Exception> {
+        let sdl_context = sdl2::init().map_err(|_| Exception::new(SDL))?;
+        let display = Display::new(&sdl_context.video().map_err(|_| Exception::new(SDL))?, options)?;
+        let speaker = Speaker::new(options, Some(&sdl_context))?;
+
+        Ok(SdlFrontend {
+            display,
+            speaker,
+            screenshots: Screenshots::new(options),
+            recordings: Recordings::new(options),
+            recorder: match &options.record {
+                Some(path) => Some(Recorder::start(Path::new(path), options.record_scale, options.tone, options.frames)?),
+                None => None,
+            },
+            sync: options.sync,
+            sdl_context,
+        })
+    }
+
+    pub fn run(&mut self, chip8: &mut Chip8) -> Result<(), Exception> {
+        let result = self.main_loop(chip8);
+
+        // Also after a crash, so that the WAV file and the recording stay readable
+        let mut result = result.and(self.speaker.close());
+        if let Some(recorder) = self.recorder.take() {
+            result = result.and(stop_recording(recorder));
+        }
+        result
+    }
+
+    fn main_loop(&mut self, chip8: &mut Chip8) -> Result<(), Exception> {
+        let mut event_pump = self.sdl_context.event_pump().map_err(|_| Exception::new(SDL))?;
+        let mut pacer = FramePacer::new();
+
+        loop {
+            for event in event_pump.poll_iter() {
+                match event {
+                    Event::Quit { .. } => {
+                        println!("Quitting");
+                        return Ok(());
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F2), repeat: false, .. } => {
+                        self.display.cycle_palette();
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F3), repeat: false, .. } => {
+                        self.display.toggle_crt();
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F12), repeat: false, .. } => {
+                        let path = self.screenshots.save(
+                            &chip8.framebuffer(), self.display.palette(), chip8.frame())?;
+                        println!("Screenshot saved to {}", path.display());
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F5), repeat: false, .. } => {
+                        self.speaker.toggle_mute();
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F6), .. } => {
+                        self.speaker.change_volume(-0.05);
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F7), .. } => {
+                        self.speaker.change_volume(0.05);
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F9), repeat: false, .. } => {
+                        match self.recorder.take() {
+                            Some(recorder) => stop_recording(recorder)?,
+                            None => {
+                                let recorder = self.recordings.start(chip8.frame())?;
+                                println!("Recording to {}", recorder.path().display());
+                                self.recorder = Some(recorder);
+                            }
+                        }
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::F11), repeat: false, .. } => {
+                        self.display.toggle_fullscreen()?;
+                    }
+                    Event::KeyDown { keycode: Some(Keycode::Return), keymod, repeat: false, .. }
+                        if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) => {
+                        self.display.toggle_fullscreen()?;
+                    }
+                    _ => {
+                        handle_key_event(&mut chip8.keyboard(), event);
+                    }
+                }
+            }
+
+            let buzzing = chip8.run_frame()?;
+            self.speaker.push_frame(buzzing)?;
+
+            self.display.render(&chip8.framebuffer())?;
+
+            if let Some(recorder) = &mut self.recorder {
+                recorder.add_frame(self.display.image(), buzzing)?;
+                if recorder.is_done() {
+                    stop_recording(self.recorder.take().unwrap())?;
+                }
+            }
+            match (self.sync, self.speaker.queued_frames()) {
+                // The audio device consumes a frame every 60th of a second
+                (SyncMode::Audio, Some(mut queued)) => while queued > AUDIO_QUEUED_FRAMES {
+                    std::thread::sleep(Duration::from_millis(1));
+                    queued = self.speaker.queued_frames().unwrap_or(0);
+                },
+                // Without an audio clock to follow, the video one is used
+                _ => pacer.wait(),
+            }
+        }
+    }
+}
+
+fn handle_key_event(keyboard: &mut Keyboard, event: Event) {
+    match event {
+        Event::KeyDown { keycode: Some(keycode), .. } => {
+            if let Some(chip8_key) = key_for_keycode(keyboard, keycode) {
+                keyboard.press(chip8_key);
+            }
+        }
+        Event::KeyUp { keycode: Some(keycode), .. } => {
+            if let Some(chip8_key) = key_for_keycode(keyboard, keycode) {
+                keyboard.release(chip8_key);
+            }
+        }
+        _ => {}
+    }
+}
+
+// SDL keycodes of printable keys are their character
+fn key_for_keycode(keyboard: &Keyboard, keycode: Keycode) -> Option<u8> {
+    char::from_u32(keycode.into_i32() as u32).and_then(|c| keyboard.key_for(c))
+}
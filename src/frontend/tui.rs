@@ -0,0 +1,239 @@
+use std::io::{self, Stdout, Write};
+use std::str::FromStr;
+use std::time::Duration;
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags};
+use crate::chip8::Chip8;
+use crate::device::framebuffer::Framebuffer;
+use crate::device::palette::{self, Palette, Rgb};
+use crate::device::speaker::{AudioOutput, Speaker};
+use crate::exceptions::Exception;
+use crate::exceptions::ExceptionType::{BadArgument, Terminal};
+use crate::frontend::FramePacer;
+use crate::frontend::tui::debugger::Debugger;
+use crate::options::Options;
+
+pub(crate) mod debugger;
+
+// Without key release events, terminals only report presses and repeats, so
+// keys are released after this many frames without a new press
+const HOLD_FRAMES: u8 = 8;
+
+// Bit of each dot of a braille character, indexed by [row][column]
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Charset {
+    // One character per 1x2 pixels, in color
+    HalfBlock,
+    // One character per 2x4 pixels, foreground color only
+    Braille,
+}
+
+impl FromStr for Charset {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<Charset, Exception> {
+        match s {
+            "half" => Ok(Charset::HalfBlock),
+            "braille" => Ok(Charset::Braille),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+// Frontend drawing in the terminal, for machines without a display server
+pub struct TuiFrontend {
+    palette: Palette,
+    charset: Charset,
+    held: [u8; 16],
+    key_releases: bool,
+    last_frame: Option<Framebuffer>,
+    buzzing: bool,
+    debugger: Option<Debugger>,
+    // Only to write --wav, the buzzer being the terminal bell otherwise
+    speaker: Option<Speaker>,
+    stdout: Stdout,
+}
+
+impl TuiFrontend {
+    pub fn new(options: &Options) -> Result<TuiFrontend, Exception> {
+        let (palettes, index) = palette::palettes(options)?;
+
+        Ok(TuiFrontend {
+            palette: palettes[index].clone(),
+            charset: options.tui_charset,
+            held: [0; 16],
+            key_releases: false,
+            last_frame: None,
+            buzzing: false,
+            debugger: options.debugger.then(Debugger::new),
+            speaker: match options.audio {
+                AudioOutput::File => Some(Speaker::new(options, None)?),
+                _ => None,
+            },
+            stdout: io::stdout(),
+        })
+    }
+
+    pub fn run(&mut self, chip8: &mut Chip8) -> Result<(), Exception> {
+        terminal::enable_raw_mode().map_err(terminal_error)?;
+        execute!(self.stdout, terminal::EnterAlternateScreen, cursor::Hide).map_err(terminal_error)?;
+        self.key_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if self.key_releases {
+            execute!(self.stdout, event::PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::REPORT_EVENT_TYPES)).map_err(terminal_error)?;
+        }
+
+        let result = self.main_loop(chip8);
+
+        // Always give the terminal back in a usable state
+        if self.key_releases {
+            let _ = execute!(self.stdout, event::PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.stdout, style::ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        match &mut self.speaker {
+            Some(speaker) => result.and(speaker.close()),
+            None => result,
+        }
+    }
+
+    fn main_loop(&mut self, chip8: &mut Chip8) -> Result<(), Exception> {
+        let mut pacer = FramePacer::new();
+
+        loop {
+            while event::poll(Duration::ZERO).map_err(terminal_error)? {
+                if let Event::Key(key) = event::read().map_err(terminal_error)? {
+                    if key.code == KeyCode::Esc
+                        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)) {
+                        return Ok(());
+                    }
+                    if let Some(debugger) = &mut self.debugger {
+                        if debugger.handle_key(chip8, key)? {
+                            continue;
+                        }
+                    }
+                    if let KeyCode::Char(c) = key.code {
+                        self.handle_key(chip8, c, key.kind);
+                    }
+                }
+            }
+
+            if !self.debugger.as_ref().is_some_and(|debugger| debugger.paused) {
+                let buzzing = chip8.run_frame()?;
+                if buzzing && !self.buzzing {
+                    queue!(self.stdout, style::Print('\x07')).map_err(terminal_error)?;
+                }
+                self.buzzing = buzzing;
+                if let Some(speaker) = &mut self.speaker {
+                    speaker.push_frame(buzzing)?;
+                }
+                if let Some(debugger) = &mut self.debugger {
+                    debugger.update(chip8);
+                }
+            }
+
+            self.draw(&chip8.framebuffer())?;
+            if let Some(debugger) = &self.debugger {
+                // Under the display, whichever characters it is drawn with
+                let height = chip8.framebuffer().height();
+                let row = match self.charset {
+                    Charset::HalfBlock => height.div_ceil(2),
+                    Charset::Braille => height.div_ceil(4),
+                };
+                debugger.draw(&mut self.stdout, chip8, row as u16).map_err(terminal_error)?;
+                self.stdout.flush().map_err(terminal_error)?;
+            }
+
+            for (key, held) in self.held.iter_mut().enumerate() {
+                if *held > 0 {
+                    *held -= 1;
+                    if *held == 0 {
+                        chip8.keyboard().release(key as u8);
+                    }
+                }
+            }
+            pacer.wait();
+        }
+    }
+
+    fn handle_key(&mut self, chip8: &mut Chip8, c: char, kind: KeyEventKind) {
+        let Some(key) = chip8.keyboard().key_for(c) else {
+            return;
+        };
+        if kind == KeyEventKind::Release {
+            chip8.keyboard().release(key);
+            self.held[key as usize] = 0;
+        } else {
+            chip8.keyboard().press(key);
+            if !self.key_releases {
+                self.held[key as usize] = HOLD_FRAMES;
+            }
+        }
+    }
+
+    fn draw(&mut self, framebuffer: &Framebuffer) -> Result<(), Exception> {
+        if self.last_frame.as_ref() == Some(framebuffer) {
+            return Ok(());
+        }
+
+        match self.charset {
+            Charset::HalfBlock => self.draw_half_blocks(framebuffer),
+            Charset::Braille => self.draw_braille(framebuffer),
+        }.map_err(terminal_error)?;
+        self.stdout.flush().map_err(terminal_error)?;
+
+        self.last_frame = Some(framebuffer.clone());
+        Ok(())
+    }
+
+    fn draw_half_blocks(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        let mut colors = None;
+        for y in (0..framebuffer.height()).step_by(2) {
+            queue!(self.stdout, cursor::MoveTo(0, (y / 2) as u16))?;
+            for x in 0..framebuffer.width() {
+                let top = self.palette.color(framebuffer.pixel(x, y));
+                let bottom = self.palette.color(framebuffer.pixel(x, y + 1));
+                // Only send the colors when they change, to keep the output small
+                if colors != Some((top, bottom)) {
+                    queue!(self.stdout,
+                        style::SetForegroundColor(color(top)),
+                        style::SetBackgroundColor(color(bottom)))?;
+                    colors = Some((top, bottom));
+                }
+                queue!(self.stdout, style::Print('▀'))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_braille(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        queue!(self.stdout,
+            style::SetForegroundColor(color(self.palette.color(1))),
+            style::SetBackgroundColor(color(self.palette.color(0))))?;
+        for y in (0..framebuffer.height()).step_by(4) {
+            queue!(self.stdout, cursor::MoveTo(0, (y / 4) as u16))?;
+            for x in (0..framebuffer.width()).step_by(2) {
+                let mut dots = 0;
+                for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, bit) in row.iter().enumerate() {
+                        if framebuffer.pixel(x + dx, y + dy) != 0 {
+                            dots |= bit;
+                        }
+                    }
+                }
+                queue!(self.stdout, style::Print(char::from_u32(0x2800 + dots).unwrap_or(' ')))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn color(rgb: Rgb) -> style::Color {
+    style::Color::Rgb { r: rgb.0, g: rgb.1, b: rgb.2 }
+}
+
+fn terminal_error(_: io::Error) -> Exception {
+    Exception::new(Terminal)
+}
@@ -0,0 +1,222 @@
+use std::io::{self, Write};
+use crossterm::{cursor, queue, style, terminal};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Attribute, Color};
+use crate::chip8::Chip8;
+use crate::chip8::memory::RAM_MAX;
+use crate::exceptions::Exception;
+
+// Hex dump of 16 rows of 16 bytes
+const ROW_BYTES: u16 = 16;
+const ROWS: u16 = 16;
+// Frames a written byte stays highlighted
+const FLASH_FRAMES: u8 = 30;
+// V0 to VF, then I, PC, DT and ST
+const REGISTERS: usize = 20;
+
+// Panel under the display with the registers, the call chain and a hex dump
+// of the memory. While paused, typing hexadecimal digits shifts them into the
+// selected byte or register.
+pub struct Debugger {
+    pub paused: bool,
+    on_registers: bool,
+    address: u16,
+    register: usize,
+    // First address of the hex dump
+    top: u16,
+    previous: Vec<u8>,
+    flashing: Vec<u8>,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            paused: false,
+            on_registers: false,
+            address: 0x200,
+            register: 0,
+            top: 0x200,
+            previous: Vec::new(),
+            flashing: vec![0; RAM_MAX],
+        }
+    }
+
+    // Compares the memory with the last update's, to flash the bytes written
+    pub fn update(&mut self, chip8: &Chip8) {
+        let memory: Vec<u8> = (0..RAM_MAX as u16).map(|address| chip8.peek(address).unwrap_or(0)).collect();
+        for (address, flash) in self.flashing.iter_mut().enumerate() {
+            if self.previous.get(address).is_some_and(|&previous| previous != memory[address]) {
+                *flash = FLASH_FRAMES;
+            } else {
+                *flash = flash.saturating_sub(1);
+            }
+        }
+        self.previous = memory;
+    }
+
+    // Returns whether the key was for the debugger rather than the keypad
+    pub fn handle_key(&mut self, chip8: &mut Chip8, key: KeyEvent) -> Result<bool, Exception> {
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::F(5) => self.paused = !self.paused,
+            KeyCode::F(6) if self.paused => {
+                chip8.step()?;
+                self.update(chip8);
+            }
+            KeyCode::Tab => self.on_registers = !self.on_registers,
+            KeyCode::Left => self.move_by(-1),
+            KeyCode::Right => self.move_by(1),
+            KeyCode::Up => self.move_by(-(ROW_BYTES as i32)),
+            KeyCode::Down => self.move_by(ROW_BYTES as i32),
+            KeyCode::PageUp => self.move_by(-((ROW_BYTES * ROWS) as i32)),
+            KeyCode::PageDown => self.move_by((ROW_BYTES * ROWS) as i32),
+            // The keypad doesn't get any character while paused
+            KeyCode::Char(c) if self.paused => match c.to_digit(16) {
+                Some(digit) => self.edit(chip8, digit as u8)?,
+                None if c == 'p' => self.select(chip8.state().pc),
+                None if c == 'i' => self.select(chip8.state().i),
+                None => {}
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn move_by(&mut self, offset: i32) {
+        if self.on_registers {
+            // Registers are on two lines of 16 and 4
+            if offset.abs() < ROW_BYTES as i32 {
+                self.register = (self.register as i32 + offset).rem_euclid(REGISTERS as i32) as usize;
+            }
+        } else {
+            self.select((self.address as i32 + offset).rem_euclid(RAM_MAX as i32) as u16);
+        }
+    }
+
+    // Selects a byte, scrolling the hex dump to it
+    fn select(&mut self, address: u16) {
+        self.on_registers = false;
+        self.address = address % RAM_MAX as u16;
+        let row = self.address - self.address % ROW_BYTES;
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + ROW_BYTES * ROWS {
+            self.top = row - ROW_BYTES * (ROWS - 1);
+        }
+    }
+
+    fn edit(&mut self, chip8: &mut Chip8, digit: u8) -> Result<(), Exception> {
+        if !self.on_registers {
+            let value = chip8.peek(self.address).unwrap_or(0) << 4 | digit;
+            chip8.poke(self.address, value)?;
+            // Not flashed, the write being ours
+            if let Some(previous) = self.previous.get_mut(self.address as usize) {
+                *previous = value;
+            }
+            return Ok(());
+        }
+
+        let mut state = chip8.state();
+        match self.register {
+            register @ 0..=15 => state.v[register] = state.v[register] << 4 | digit,
+            // I and PC keep their 12 bits
+            16 => state.i = (state.i << 4 | digit as u16) & 0xFFF,
+            17 => state.pc = (state.pc << 4 | digit as u16) & 0xFFF,
+            18 => state.dt = state.dt << 4 | digit,
+            _ => state.st = state.st << 4 | digit,
+        }
+        chip8.set_state(&state);
+        Ok(())
+    }
+
+    pub fn draw(&self, out: &mut impl Write, chip8: &Chip8, row: u16) -> io::Result<()> {
+        let state = chip8.state();
+        let mut line = row;
+
+        next_line(out, &mut line)?;
+        let help = if self.paused {
+            "PAUSED   F5 run  F6 step  0-F edit  p/i go to PC/I  Tab memory/registers"
+        } else {
+            "RUNNING  F5 pause  arrows PgUp PgDn move  Tab memory/registers"
+        };
+        queue!(out, style::Print(help))?;
+
+        let registers: Vec<(String, String)> = state.v.iter().enumerate()
+            .map(|(register, v)| (format!("V{:X}", register), format!("{:02X}", v)))
+            .chain([
+                (String::from("I"), format!("{:03X}", state.i)),
+                (String::from("PC"), format!("{:03X}", state.pc)),
+                (String::from("DT"), format!("{:02X}", state.dt)),
+                (String::from("ST"), format!("{:02X}", state.st)),
+            ])
+            .collect();
+        for (index, (name, value)) in registers.iter().enumerate() {
+            if index % 16 == 0 {
+                next_line(out, &mut line)?;
+            }
+            queue!(out, style::Print(format!("{}=", name)))?;
+            if self.on_registers && index == self.register {
+                queue!(out, style::SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(out, style::Print(value), style::SetAttribute(Attribute::Reset), style::Print(' '))?;
+        }
+
+        let frames = chip8.call_stack().frames();
+        queue!(out, style::Print(format!(" SP={}", frames.len())))?;
+        // Innermost first, as in the call chain printed after a crash
+        for frame in frames.iter().rev() {
+            next_line(out, &mut line)?;
+            queue!(out, style::Print(format!("  {:03X}  called from {:03X}, returns to {:03X}",
+                frame.subroutine, frame.return_address.wrapping_sub(2), frame.return_address)))?;
+        }
+        next_line(out, &mut line)?;
+        queue!(out, style::Print("  main"))?;
+
+        next_line(out, &mut line)?;
+        queue!(out, style::Print("     "))?;
+        for column in 0..ROW_BYTES {
+            queue!(out, style::Print(format!(" {:X} ", column)))?;
+        }
+        for row in 0..ROWS {
+            let start = self.top + row * ROW_BYTES;
+            if start as usize >= RAM_MAX {
+                break;
+            }
+            next_line(out, &mut line)?;
+            queue!(out, style::Print(format!("{:03X}  ", start)))?;
+            for address in start..start + ROW_BYTES {
+                self.draw_byte(out, chip8, address, state.pc, state.i)?;
+            }
+        }
+        // The call chain changes length, leaving lines of the previous panel
+        next_line(out, &mut line)?;
+        queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))
+    }
+
+    fn draw_byte(&self, out: &mut impl Write, chip8: &Chip8, address: u16, pc: u16, i: u16) -> io::Result<()> {
+        if address == pc || address == pc.wrapping_add(1) {
+            queue!(out, style::SetBackgroundColor(Color::DarkGreen))?;
+        } else if address == i {
+            queue!(out, style::SetBackgroundColor(Color::DarkBlue))?;
+        }
+        if self.flashing[address as usize] > 0 {
+            queue!(out, style::SetForegroundColor(Color::Red), style::SetAttribute(Attribute::Bold))?;
+        }
+        if !self.on_registers && address == self.address {
+            queue!(out, style::SetAttribute(Attribute::Reverse))?;
+        }
+        let value = chip8.peek(address).unwrap_or(0);
+        queue!(out, style::Print(format!("{:02X}", value)),
+            style::SetAttribute(Attribute::Reset), style::ResetColor, style::Print(' '))
+    }
+}
+
+// Moves to the start of a cleared line
+fn next_line(out: &mut impl Write, line: &mut u16) -> io::Result<()> {
+    queue!(out, style::SetAttribute(Attribute::Reset), style::ResetColor,
+        cursor::MoveTo(0, *line), terminal::Clear(terminal::ClearType::UntilNewLine))?;
+    *line += 1;
+    Ok(())
+}
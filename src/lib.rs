@@ -0,0 +1,12 @@
+// The emulator core, without any frontend, so that the fuzz targets can drive
+// it without linking SDL
+pub mod chip8;
+pub mod exceptions;
+pub mod device {
+    pub mod framebuffer;
+    pub mod keyboard;
+    pub mod sprite;
+}
+
+pub use chip8::Chip8;
+pub use exceptions::Exception;
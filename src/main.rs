@@ -1,14 +1,104 @@
-mod chip8;
-mod exceptions;
+use std::fs;
+use chip_eight::{chip8, exceptions};
+use chip8::Chip8;
+use chip8::font::Font;
+use chip8::tracer::Tracer;
+use exceptions::Exception;
+use exceptions::ExceptionType::File as FileError;
+use frontend::Frontend;
+use frontend::headless::HeadlessFrontend;
+use frontend::sdl::SdlFrontend;
+use frontend::tui::TuiFrontend;
+use options::{Command, Options};
+
 mod device;
+mod options;
+mod config;
+mod frontend;
+mod capture;
+mod analysis;
 
 fn main() {
-    //let c8 = chip8::Chip8::new("roms/1-chip8-logo.ch8");
-    //let c8 = chip8::Chip8::new("roms/IBM_Logo.ch8");
-    //let c8 = chip8::Chip8::new("roms/3-corax+.ch8");
-    //let c8 = chip8::Chip8::new("roms/4-flags.ch8");
-    //let c8 = chip8::Chip8::new("roms/5-quirks.ch8");
-    let c8 = chip8::Chip8::new("roms/6-keypad.ch8");
-    //let c8 = chip8::Chip8::new("roms/7-beep.ch8");
-    c8.unwrap().start().expect("Chip8 crashed");
-}
\ No newline at end of file
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, options::USAGE);
+            std::process::exit(1);
+        }
+    };
+    let tool = match options.command {
+        Command::Run | Command::Diff => None,
+        Command::Analyze => Some(analysis::run(&options)),
+        Command::Lint => Some(analysis::lint::run(&options)),
+        Command::Sprites => Some(analysis::sprites::run(&options)),
+    };
+    if let Some(result) = tool {
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut c8 = match new_chip8(&options) {
+        Ok(c8) => c8,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if options.command == Command::Diff {
+        let result = analysis::diff::run(&options, &mut c8);
+        // Flushes the trace before exiting
+        drop(c8);
+        match result {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let result = match options.frontend {
+        Frontend::Sdl => SdlFrontend::new(&options).and_then(|mut sdl| sdl.run(&mut c8)),
+        Frontend::Tui => TuiFrontend::new(&options).and_then(|mut tui| tui.run(&mut c8)),
+        Frontend::Headless => HeadlessFrontend::new(&options).and_then(|mut headless| headless.run(&mut c8)),
+    };
+    // Also printed after a crash, to see what led to it
+    if let Some(report) = c8.profile_report() {
+        print!("{}", report);
+    }
+    if let Some(coverage) = c8.coverage_mut() {
+        if let Err(e) = analysis::coverage::report(&options, coverage) {
+            eprintln!("Coverage not saved: {}", e);
+        }
+    }
+    if let Err(e) = &result {
+        eprint!("{}\n{}", e, c8.call_stack());
+    }
+    result.expect("Chip8 crashed");
+}
+
+// Machine set up as the options say, with the debugging tools they ask for
+fn new_chip8(options: &Options) -> Result<Chip8, Exception> {
+    let rom = fs::read(&options.rom).map_err(|_| Exception::new(FileError))?;
+    let font = match &options.font_file {
+        Some(path) => Font::load(path, options.font_address.0)?,
+        None => Font::new(options.font, options.font_address.0),
+    };
+    let mut c8 = Chip8::new(&rom, &font, options.memory_layout, options.reserved_writes, options.out_of_range)?;
+    c8.set_stack_depth(options.stack_depth);
+    if options.profile {
+        c8.profile();
+    }
+    if let Some(path) = &options.trace {
+        c8.trace(Tracer::new(path, options.trace_filter, options.trace_format)?);
+    }
+    if options.coverage.is_some() {
+        c8.cover(&rom);
+    }
+    Ok(c8)
+}
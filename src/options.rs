@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use crate::analysis::sprites::SpriteSize;
+use crate::capture::recorder::RecordFormat;
+use crate::chip8::font::{DEFAULT_FONT_ADDRESS, FontSet};
+use crate::chip8::memory::{Address, OutOfRange, ReservedWrites};
+use crate::chip8::memory::layout::Layout;
+use crate::chip8::stack::DEFAULT_DEPTH;
+use crate::chip8::tracer::{AddressRange, TraceFilter, TraceFormat};
+use crate::config::{Config, DEFAULT_PATH};
+use crate::device::crt::CrtSettings;
+use crate::device::filter::FilterMode;
+use crate::device::palette::Rgb;
+use crate::device::speaker::{AudioOutput, ToneSettings, Waveform};
+use crate::exceptions::Exception;
+use crate::frontend::{Frontend, SyncMode};
+use crate::frontend::tui::Charset;
+use crate::exceptions::ExceptionType::BadArgument;
+
+pub const USAGE: &str = "Usage: ChipEight [analyze|lint|diff|sprites] [--config FILE] [--frontend sdl|tui|headless]
+                 [--tui-charset half|braille] [--debugger] [--frames N] [--stack-depth N] [--profile]
+                 [--memory-layout modern|vip] [--reserved-writes allow|warn|trap]
+                 [--out-of-range error|wrap] [--font vip|dream6800|eti660|schip]
+                 [--font-file FILE] [--font-address 1AF]
+                 [--coverage FILE] [--lcov FILE] [--source-map FILE]
+                 [--trace FILE|-] [--trace-format delta|state] [--trace-addresses 200-2FF]
+                 [--trace-opcodes 1,2,D] [--trace-frames 100-200] [--reference FILE]
+                 [--steps N] [--screenshot]
+                 [--screenshot-scale N] [--capture-dir DIR] [--record FILE]
+                 [--record-scale N] [--record-format gif|y4m|ppm]
+                 [--audio sdl|null|file] [--wav FILE] [--sample-rate 1-192000]
+                 [--sync video|audio] [--pitch HZ] [--volume 0-1]
+                 [--waveform square|triangle|sine|sawtooth|noise]
+                 [--scale N] [--integer-scale] [--fullscreen]
+                 [--palette NAME] [--foreground RRGGBB] [--background RRGGBB]
+                 [--foreground2 RRGGBB] [--blend RRGGBB]
+                 [--filter off|phosphor|blend|max] [--filter-frames N]
+                 [--crt] [--crt-scanlines X] [--crt-bloom X] [--crt-curvature X]
+                 [--crt-vignette X] [--dot FILE] [--map FILE]
+                 [--sprite-range 300-37F] [--sprite-size 8xN|16x16]
+                 [--sheet FILE] [--sheet-scale N] <rom>
+
+`analyze` walks the ROM without running it, prints its code/data map (or
+saves it with --map) and saves its control flow graph with --dot. `lint` reports the instruction set
+the ROM uses and the patterns which depend on the interpreter's quirks.
+--coverage adds each run's counts to FILE, prints the annotated disassembly
+and saves an lcov report with --lcov, using `address file:line` source maps.
+`diff` runs --steps instructions, comparing the CPU state after each one with
+a --reference trace as written by --trace-format state.
+`sprites` cuts the --sprite-range of the memory into sprites, or without a
+range collects every sprite drawn during --frames frames, and saves them as a
+PNG sheet with --sheet.
+--stack-depth is the number of nested subroutine calls: 12 on the VIP, 16 on SCHIP.
+The vip memory layout keeps EA0-FFF for the interpreter's stack and display;
+--reserved-writes sets what happens when the ROM writes there or to the font.
+--capture-dir, also accepted as --screenshot-dir, is where captures are saved.
+--font-file loads the 16 digits of 5 bytes each from FILE instead of --font.
+--debugger shows the registers and memory under the TUI display, and is only
+accepted with --frontend tui: F5 pauses, F6 steps, and while paused hexadecimal
+digits edit the selected byte or register.
+
+Every option can also be set as `key = value` in the config file, globally or
+in a `[rom.ch8]` section for a single ROM.
+Palettes: green, amber, white, octo, gameboy";
+
+// Options which don't take a value
+const FLAGS: [&str; 6] = ["integer-scale", "fullscreen", "crt", "screenshot", "profile", "debugger"];
+const KEYS: [&str; 58] = [
+    "audio", "wav", "sample-rate", "sync", "pitch", "volume", "waveform",
+    "frontend", "tui-charset", "debugger", "frames", "stack-depth", "profile",
+    "memory-layout", "reserved-writes", "out-of-range",
+    "font", "font-file", "font-address",
+    "coverage", "lcov", "source-map",
+    "trace", "trace-format", "trace-addresses", "trace-opcodes", "trace-frames",
+    "reference", "steps",
+    "screenshot", "screenshot-scale", "capture-dir", "screenshot-dir",
+    "record", "record-scale", "record-format",
+    "scale", "integer-scale", "fullscreen",
+    "palette", "foreground", "background", "foreground2", "blend",
+    "filter", "filter-frames",
+    "crt", "crt-scanlines", "crt-bloom", "crt-curvature", "crt-vignette",
+    "dot", "map",
+    "sprite-range", "sprite-size", "sheet", "sheet-scale",
+    "config",
+];
+// Highest --sample-rate, far above what audio devices use
+const MAX_SAMPLE_RATE: u32 = 192000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Run,
+    Analyze,
+    Lint,
+    Diff,
+    Sprites,
+}
+
+impl FromStr for Command {
+    type Err = Exception;
+
+    fn from_str(s: &str) -> Result<Command, Exception> {
+        match s {
+            "run" => Ok(Command::Run),
+            "analyze" => Ok(Command::Analyze),
+            "lint" | "inspect" => Ok(Command::Lint),
+            "diff" => Ok(Command::Diff),
+            "sprites" => Ok(Command::Sprites),
+            _ => Err(Exception::new(BadArgument)),
+        }
+    }
+}
+
+pub struct Options {
+    pub command: Command,
+    pub rom: String,
+    pub frontend: Frontend,
+    pub tui_charset: Charset,
+    pub debugger: bool,
+    pub frames: Option<u64>,
+    pub stack_depth: usize,
+    pub memory_layout: Layout,
+    pub reserved_writes: ReservedWrites,
+    pub out_of_range: OutOfRange,
+    pub font: FontSet,
+    pub font_file: Option<String>,
+    pub font_address: Address,
+    pub profile: bool,
+    pub coverage: Option<String>,
+    pub lcov: Option<String>,
+    pub source_map: Option<String>,
+    pub trace: Option<String>,
+    pub trace_format: TraceFormat,
+    pub trace_filter: TraceFilter,
+    pub reference: Option<String>,
+    pub steps: Option<u64>,
+    pub screenshot: bool,
+    pub screenshot_scale: u32,
+    pub capture_dir: String,
+    pub record: Option<String>,
+    pub record_scale: u32,
+    pub record_format: RecordFormat,
+    pub audio: AudioOutput,
+    pub wav: Option<String>,
+    pub sample_rate: u32,
+    pub sync: SyncMode,
+    pub tone: ToneSettings,
+    pub scale: u32,
+    pub integer_scale: bool,
+    pub fullscreen: bool,
+    pub palette: String,
+    pub foreground: Option<Rgb>,
+    pub background: Option<Rgb>,
+    pub foreground2: Option<Rgb>,
+    pub blend: Option<Rgb>,
+    pub filter: FilterMode,
+    pub filter_frames: usize,
+    pub crt: bool,
+    pub crt_settings: CrtSettings,
+    pub dot: Option<String>,
+    pub map: Option<String>,
+    pub sprite_range: Option<AddressRange>,
+    pub sprite_size: SpriteSize,
+    pub sheet: Option<String>,
+    pub sheet_scale: u32,
+}
+
+impl Options {
+    // Command line options take precedence over the config file
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Options, Exception> {
+        let mut rom = None;
+        let mut settings = HashMap::new();
+
+        // The command is optional, and comes before everything else
+        let mut args = args.peekable();
+        let command = match args.peek().map(|arg| arg.parse()) {
+            Some(Ok(command)) => {
+                args.next();
+                command
+            }
+            _ => Command::Run,
+        };
+        while let Some(arg) = args.next() {
+            if let Some(key) = arg.strip_prefix("--") {
+                if !KEYS.contains(&key) {
+                    return Err(Exception::new(BadArgument));
+                }
+                let value = if FLAGS.contains(&key) {
+                    String::from("true")
+                } else {
+                    args.next().ok_or(Exception::new(BadArgument))?
+                };
+                settings.insert(key.to_string(), value);
+            } else {
+                rom = Some(arg);
+            }
+        }
+        let rom: String = rom.ok_or(Exception::new(BadArgument))?;
+
+        let config = match settings.get("config") {
+            Some(path) => Config::load(path)?,
+            None if Path::new(DEFAULT_PATH).exists() => Config::load(DEFAULT_PATH)?,
+            None => Config::new(),
+        };
+        for (key, value) in config.settings(&rom) {
+            if !KEYS.contains(&key.as_str()) {
+                return Err(Exception::new(BadArgument));
+            }
+            settings.entry(key).or_insert(value);
+        }
+
+        let frontend = Self::get(&settings, "frontend")?.unwrap_or(Frontend::Sdl);
+        let wav: Option<String> = Self::get(&settings, "wav")?;
+        // Only the SDL frontend has an audio device to play the sound on
+        let audio = match Self::get(&settings, "audio")? {
+            Some(audio) => audio,
+            None if wav.is_some() => AudioOutput::File,
+            None if frontend == Frontend::Sdl => AudioOutput::Sdl,
+            None => AudioOutput::Null,
+        };
+
+        let options = Options {
+            command,
+            rom,
+            frontend,
+            tui_charset: Self::get(&settings, "tui-charset")?.unwrap_or(Charset::HalfBlock),
+            debugger: Self::get(&settings, "debugger")?.unwrap_or(false),
+            frames: Self::get(&settings, "frames")?,
+            stack_depth: Self::get(&settings, "stack-depth")?.unwrap_or(DEFAULT_DEPTH),
+            memory_layout: Self::get(&settings, "memory-layout")?.unwrap_or(Layout::Modern),
+            reserved_writes: Self::get(&settings, "reserved-writes")?.unwrap_or(ReservedWrites::Allow),
+            out_of_range: Self::get(&settings, "out-of-range")?.unwrap_or(OutOfRange::Error),
+            font: Self::get(&settings, "font")?.unwrap_or(FontSet::Schip),
+            font_file: Self::get(&settings, "font-file")?,
+            font_address: Self::get(&settings, "font-address")?.unwrap_or(Address(DEFAULT_FONT_ADDRESS)),
+            profile: Self::get(&settings, "profile")?.unwrap_or(false),
+            coverage: Self::get(&settings, "coverage")?,
+            lcov: Self::get(&settings, "lcov")?,
+            source_map: Self::get(&settings, "source-map")?,
+            trace: Self::get(&settings, "trace")?,
+            trace_format: Self::get(&settings, "trace-format")?.unwrap_or(TraceFormat::Delta),
+            trace_filter: TraceFilter {
+                addresses: Self::get(&settings, "trace-addresses")?,
+                classes: Self::get(&settings, "trace-opcodes")?,
+                frames: Self::get(&settings, "trace-frames")?,
+            },
+            reference: Self::get(&settings, "reference")?,
+            steps: Self::get(&settings, "steps")?,
+            screenshot: Self::get(&settings, "screenshot")?.unwrap_or(false),
+            screenshot_scale: Self::get(&settings, "screenshot-scale")?.unwrap_or(10),
+            // `screenshot-dir` is the name from before recordings were saved there too
+            capture_dir: match Self::get(&settings, "capture-dir")? {
+                Some(directory) => directory,
+                None => Self::get(&settings, "screenshot-dir")?.unwrap_or(String::from(".")),
+            },
+            record: Self::get(&settings, "record")?,
+            record_scale: Self::get(&settings, "record-scale")?.unwrap_or(4),
+            record_format: Self::get(&settings, "record-format")?.unwrap_or(RecordFormat::Gif),
+            audio,
+            wav,
+            sample_rate: Self::get(&settings, "sample-rate")?.unwrap_or(44100),
+            sync: Self::get(&settings, "sync")?.unwrap_or(SyncMode::Video),
+            tone: ToneSettings {
+                pitch: Self::get(&settings, "pitch")?.unwrap_or(440.0),
+                volume: Self::get::<f32>(&settings, "volume")?.unwrap_or(0.25).clamp(0.0, 1.0),
+                waveform: Self::get(&settings, "waveform")?.unwrap_or(Waveform::Square),
+            },
+            scale: Self::get(&settings, "scale")?.unwrap_or(15),
+            integer_scale: Self::get(&settings, "integer-scale")?.unwrap_or(false),
+            fullscreen: Self::get(&settings, "fullscreen")?.unwrap_or(false),
+            palette: Self::get(&settings, "palette")?.unwrap_or(String::from("green")),
+            foreground: Self::get(&settings, "foreground")?,
+            background: Self::get(&settings, "background")?,
+            foreground2: Self::get(&settings, "foreground2")?,
+            blend: Self::get(&settings, "blend")?,
+            filter: Self::get(&settings, "filter")?.unwrap_or(FilterMode::Off),
+            filter_frames: Self::get(&settings, "filter-frames")?.unwrap_or(4),
+            crt: Self::get(&settings, "crt")?.unwrap_or(false),
+            crt_settings: CrtSettings {
+                scanlines: Self::get(&settings, "crt-scanlines")?.unwrap_or(0.4),
+                bloom: Self::get(&settings, "crt-bloom")?.unwrap_or(0.3),
+                curvature: Self::get(&settings, "crt-curvature")?.unwrap_or(0.2),
+                vignette: Self::get(&settings, "crt-vignette")?.unwrap_or(0.3),
+            },
+            dot: Self::get(&settings, "dot")?,
+            map: Self::get(&settings, "map")?,
+            sprite_range: Self::get(&settings, "sprite-range")?,
+            sprite_size: Self::get(&settings, "sprite-size")?.unwrap_or(SpriteSize { width: 8, height: 8 }),
+            sheet: Self::get(&settings, "sheet")?,
+            sheet_scale: Self::get(&settings, "sheet-scale")?.unwrap_or(8),
+        };
+        if options.scale == 0 || options.stack_depth == 0
+            || !(1..=MAX_SAMPLE_RATE).contains(&options.sample_rate)
+            || !options.tone.pitch.is_finite() || options.tone.pitch <= 0.0
+            || (options.audio == AudioOutput::File && options.wav.is_none())
+            || (options.debugger && options.command == Command::Run && options.frontend != Frontend::Tui) {
+            return Err(Exception::new(BadArgument));
+        }
+        Ok(options)
+    }
+
+    fn get<T: FromStr>(settings: &HashMap<String, String>, key: &str) -> Result<Option<T>, Exception> {
+        match settings.get(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| Exception::new(BadArgument)),
+            None => Ok(None),
+        }
+    }
+}